@@ -1,35 +1,85 @@
+use crate::{
+    ewmh::FocusStealing,
+    focus::FocusMode,
+    layout::{Gaps, Layout, Tiling, MAX_BORDER, MAX_GAP, MAX_NMASTER},
+};
 use anyhow::{bail, Context};
+use log::warn;
 use std::{env, fs, path::PathBuf};
 
+/// Optional tiling defaults that override the global values for one workspace.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct WorkspaceTiling {
+    pub layout: Option<Layout>,
+    pub master_ratio: Option<f32>,
+    pub nmaster: Option<usize>,
+}
+
+/// A command whose window, found by its `WM_CLASS` instance name, is
+/// toggled on demand.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Scratchpad {
+    pub command: Vec<String>,
+    pub instance: String,
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct Config {
     pub terminal: Vec<String>,
     pub file_manager: Vec<String>,
     pub browser: Vec<String>,
     pub launcher: Vec<String>,
+    /// Window switcher used to pick a minimized client to restore.
+    pub window_picker: Vec<String>,
     pub modifier: String,
-    pub gaps: u32,
+    pub gaps: Gaps,
+    pub smart_gaps: bool,
     pub border_width: u32,
+    pub smart_borders: bool,
+    /// Move the pointer to the client focused from the keyboard.
+    pub warp_pointer: bool,
     pub focused_border: u32,
     pub unfocused_border: u32,
+    pub urgent_border: u32,
+    pub unresponsive_border: u32,
+    pub focus_mode: FocusMode,
+    /// Policy for new or activated windows taking focus on their own.
+    pub focus_stealing: FocusStealing,
     pub master_ratio: f32,
+    pub nmaster: usize,
+    pub layout: Layout,
+    pub workspace_tiling: [WorkspaceTiling; 9],
     pub workspaces: usize,
+    pub scratchpads: [Scratchpad; 9],
     pub autostart: Option<PathBuf>,
 }
 impl Default for Config {
     fn default() -> Self {
+        let tiling = Tiling::default();
         Self {
             terminal: vec!["kitty".into()],
             file_manager: vec!["thunar".into()],
             browser: vec!["firefox-esr".into()],
             launcher: vec!["boringwm-rofi".into()],
+            window_picker: vec!["rofi".into(), "-show".into(), "window".into()],
             modifier: "Mod4".into(),
-            gaps: 8,
-            border_width: 2,
+            gaps: tiling.gaps,
+            smart_gaps: false,
+            border_width: tiling.border_width,
+            smart_borders: false,
+            warp_pointer: false,
             focused_border: 0x88ccff,
             unfocused_border: 0x333333,
-            master_ratio: 0.6,
+            urgent_border: 0xff5555,
+            unresponsive_border: 0xffaa00,
+            focus_mode: FocusMode::default(),
+            focus_stealing: FocusStealing::default(),
+            master_ratio: tiling.master_ratio,
+            nmaster: tiling.nmaster,
+            layout: tiling.layout,
+            workspace_tiling: Default::default(),
             workspaces: 9,
+            scratchpads: Default::default(),
             autostart: None,
         }
     }
@@ -58,6 +108,22 @@ impl Config {
         c.set_default_autostart();
         Ok(c)
     }
+    /// Initial tiling of `workspace`, falling back to the global values.
+    pub fn tiling(&self, workspace: usize) -> Tiling {
+        let o = self
+            .workspace_tiling
+            .get(workspace)
+            .copied()
+            .unwrap_or_default();
+        Tiling {
+            layout: o.layout.unwrap_or(self.layout),
+            master_ratio: o.master_ratio.unwrap_or(self.master_ratio),
+            nmaster: o.nmaster.unwrap_or(self.nmaster),
+            gaps: self.gaps,
+            gaps_enabled: true,
+            border_width: self.border_width,
+        }
+    }
     fn set_default_autostart(&mut self) {
         if self.autostart.is_none() {
             self.autostart =
@@ -66,6 +132,8 @@ impl Config {
     }
     fn parse(input: &str) -> anyhow::Result<Self> {
         let mut c = Self::default();
+        // `inner_gaps` and `outer_gaps` beat `gaps` wherever they appear.
+        let (mut gaps, mut inner_gaps, mut outer_gaps) = (None, None, None);
         for (line_number, raw) in input.lines().enumerate() {
             let line = raw.trim();
             if line.is_empty() || line.starts_with('#') {
@@ -81,25 +149,80 @@ impl Config {
                 "file_manager" => c.file_manager = parse_command(value)?,
                 "browser" => c.browser = parse_command(value)?,
                 "launcher" => c.launcher = parse_command(value)?,
+                "window_picker" => c.window_picker = parse_command(value)?,
                 "modifier" => c.modifier = parse_string(value)?,
-                "gaps" => c.gaps = value.parse()?,
+                "gaps" => gaps = Some(value.parse()?),
+                "inner_gaps" => inner_gaps = Some(value.parse()?),
+                "outer_gaps" => outer_gaps = Some(value.parse()?),
+                "smart_gaps" => c.smart_gaps = parse_bool(value)?,
                 "border_width" => c.border_width = value.parse()?,
+                "smart_borders" => c.smart_borders = parse_bool(value)?,
+                "warp_pointer" => c.warp_pointer = or_default(key, parse_bool(value)),
                 "focused_border" => c.focused_border = parse_color(value)?,
                 "unfocused_border" => c.unfocused_border = parse_color(value)?,
+                "urgent_border" => c.urgent_border = parse_color(value)?,
+                "unresponsive_border" => c.unresponsive_border = parse_color(value)?,
+                "focus_mode" => c.focus_mode = or_default(key, parse_focus_mode(value)),
+                "focus_stealing" => c.focus_stealing = or_default(key, parse_focus_stealing(value)),
                 "master_ratio" => c.master_ratio = value.parse()?,
+                "nmaster" => c.nmaster = value.parse()?,
+                "layout" => c.layout = parse_layout(value)?,
                 "workspaces" => c.workspaces = value.parse()?,
                 "autostart" => c.autostart = Some(PathBuf::from(parse_string(value)?)),
-                _ => bail!("line {}: unknown field {key}", line_number + 1),
+                _ => {
+                    if let Some((index, field)) = indexed_field(key, "workspace_") {
+                        let o = &mut c.workspace_tiling[index];
+                        match field {
+                            "layout" => o.layout = Some(parse_layout(value)?),
+                            "master_ratio" => o.master_ratio = Some(value.parse()?),
+                            "nmaster" => o.nmaster = Some(value.parse()?),
+                            _ => bail!("line {}: unknown field {key}", line_number + 1),
+                        }
+                    } else if let Some((index, field)) = indexed_field(key, "scratchpad_") {
+                        let pad = &mut c.scratchpads[index];
+                        match field {
+                            "" => pad.command = parse_command(value)?,
+                            "instance" => pad.instance = parse_string(value)?,
+                            _ => bail!("line {}: unknown field {key}", line_number + 1),
+                        }
+                    } else {
+                        bail!("line {}: unknown field {key}", line_number + 1)
+                    }
+                }
             }
         }
+        if let Some(gaps) = gaps {
+            c.gaps = Gaps::uniform(gaps);
+        }
+        c.gaps.inner = inner_gaps.unwrap_or(c.gaps.inner);
+        c.gaps.outer = outer_gaps.unwrap_or(c.gaps.outer);
         if c.workspaces == 0 || c.workspaces > 9 {
             bail!("workspaces must be between 1 and 9")
         }
-        if c.gaps > 100 || c.border_width > 20 {
-            bail!("gaps must be <= 100 and border_width <= 20")
+        if c.gaps.inner > MAX_GAP || c.gaps.outer > MAX_GAP || c.border_width > MAX_BORDER {
+            bail!("gaps must be <= {MAX_GAP} and border_width <= {MAX_BORDER}")
+        }
+        for (index, o) in c.workspace_tiling.iter().enumerate() {
+            if index >= c.workspaces && *o != WorkspaceTiling::default() {
+                bail!("workspace_{} settings exceed workspaces", index + 1)
+            }
+        }
+        for ws in 0..c.workspaces {
+            let t = c.tiling(ws);
+            if !(0.2..=0.8).contains(&t.master_ratio) {
+                bail!("master_ratio must be between 0.2 and 0.8")
+            }
+            if t.nmaster > MAX_NMASTER {
+                bail!("nmaster must be <= {MAX_NMASTER}")
+            }
         }
-        if !(0.2..=0.8).contains(&c.master_ratio) {
-            bail!("master_ratio must be between 0.2 and 0.8")
+        for (index, pad) in c.scratchpads.iter().enumerate() {
+            if pad.command.is_empty() != pad.instance.is_empty() {
+                bail!(
+                    "scratchpad_{0} and scratchpad_{0}_instance must be set together",
+                    index + 1
+                )
+            }
         }
         if c.modifier != "Mod4" {
             bail!("only modifier = \"Mod4\" is currently supported")
@@ -107,6 +230,42 @@ impl Config {
         Ok(c)
     }
 }
+/// Split `<prefix><n>_<field>` or `<prefix><n>` into a zero-based index and
+/// the (possibly empty) field name.
+fn indexed_field<'a>(key: &'a str, prefix: &str) -> Option<(usize, &'a str)> {
+    let rest = key.strip_prefix(prefix)?;
+    let (number, field) = rest.split_once('_').unwrap_or((rest, ""));
+    let index = number.parse::<usize>().ok()?.checked_sub(1)?;
+    (index < 9).then_some((index, field))
+}
+fn parse_layout(v: &str) -> anyhow::Result<Layout> {
+    let name = parse_string(v)?;
+    Layout::parse(&name).with_context(|| format!("unknown layout {name}"))
+}
+fn parse_focus_mode(v: &str) -> anyhow::Result<FocusMode> {
+    let name = parse_string(v)?;
+    FocusMode::parse(&name).with_context(|| format!("unknown focus_mode {name}"))
+}
+fn parse_focus_stealing(v: &str) -> anyhow::Result<FocusStealing> {
+    let name = parse_string(v)?;
+    FocusStealing::parse(&name).with_context(|| format!("unknown focus_stealing policy {name}"))
+}
+/// Value of a focus behaviour option. A bad value only costs that
+/// behaviour, so it is logged and replaced by the default instead of
+/// refusing to start.
+fn or_default<T: Default>(key: &str, parsed: anyhow::Result<T>) -> T {
+    parsed.unwrap_or_else(|e| {
+        warn!("{key}: {e:#}; using the default");
+        T::default()
+    })
+}
+fn parse_bool(v: &str) -> anyhow::Result<bool> {
+    match v {
+        "true" => Ok(true),
+        "false" => Ok(false),
+        _ => bail!("expected true or false"),
+    }
+}
 fn parse_string(v: &str) -> anyhow::Result<String> {
     let v = v.trim();
     if v.len() < 2
@@ -145,14 +304,17 @@ mod tests {
     #[test]
     fn defaults_are_valid() {
         Config::parse("").unwrap();
+        assert_eq!(Config::default().tiling(0), Tiling::default());
     }
     #[test]
     fn parses_commands_and_colors() {
         let c = Config::parse(
-            "terminal = [\"xterm\", \"-name\", \"boring\"]\nfocused_border = \"#abcdef\"",
+            "terminal = [\"xterm\", \"-name\", \"boring\"]\nfocused_border = \"#abcdef\"\nwindow_picker = [\"dmenu-windows\"]\nurgent_border = \"0xff0000\"",
         )
         .unwrap();
         assert_eq!(c.terminal[1], "-name");
+        assert_eq!(c.window_picker, ["dmenu-windows"]);
+        assert_eq!(c.urgent_border, 0xff0000);
         assert_eq!(c.focused_border, 0xabcdef);
     }
     #[test]
@@ -165,4 +327,103 @@ mod tests {
         assert!(Config::parse("master_ratio = 0.9").is_err());
         assert!(Config::parse("terminal = []").is_err());
     }
+    #[test]
+    fn workspace_tiling_overrides_globals() {
+        let c = Config::parse(
+            "layout = \"dwindle\"\nworkspace_2_layout = \"master_stack\"\nworkspace_2_nmaster = 2\nmaster_ratio = 0.5",
+        )
+        .unwrap();
+        assert_eq!(c.tiling(0).layout, Layout::Dwindle);
+        assert_eq!(c.tiling(0).master_ratio, 0.5);
+        assert_eq!(c.tiling(1).layout, Layout::MasterStack);
+        assert_eq!(c.tiling(1).nmaster, 2);
+        assert_eq!(c.tiling(1).master_ratio, 0.5);
+    }
+    #[test]
+    fn rejects_invalid_workspace_tiling() {
+        assert!(Config::parse("workspace_0_nmaster = 1").is_err());
+        assert!(Config::parse("workspace_3_ratio = 0.5").is_err());
+        assert!(Config::parse("workspace_3_master_ratio = 0.9").is_err());
+        assert!(Config::parse("workspaces = 2\nworkspace_3_nmaster = 2").is_err());
+        assert!(Config::parse("layout = \"tabs\"").is_err());
+    }
+    #[test]
+    fn parses_gap_variants_and_smart_options() {
+        let c = Config::parse("gaps = 6\nouter_gaps = 12\nsmart_gaps = true").unwrap();
+        assert_eq!(
+            c.gaps,
+            Gaps {
+                inner: 6,
+                outer: 12
+            }
+        );
+        assert!(c.smart_gaps && !c.smart_borders);
+        assert!(Config::parse("smart_borders = yes").is_err());
+        assert!(Config::parse("inner_gaps = 101").is_err());
+    }
+    #[test]
+    fn specific_gaps_win_in_any_order() {
+        let expected = Gaps {
+            inner: 4,
+            outer: 10,
+        };
+        assert_eq!(
+            Config::parse("inner_gaps = 4\ngaps = 10").unwrap().gaps,
+            expected
+        );
+        assert_eq!(
+            Config::parse("gaps = 10\ninner_gaps = 4").unwrap().gaps,
+            expected
+        );
+        assert_eq!(Config::parse("outer_gaps = 3").unwrap().gaps.outer, 3);
+    }
+    #[test]
+    fn parses_focus_stealing() {
+        let parse = |v: &str| {
+            Config::parse(&format!("focus_stealing = {v}"))
+                .unwrap()
+                .focus_stealing
+        };
+        assert_eq!(parse("\"strict\""), FocusStealing::Strict);
+        assert_eq!(parse("\"off\""), FocusStealing::Off);
+        assert_eq!(parse("\"never\""), FocusStealing::default());
+        assert_eq!(parse("strict"), FocusStealing::default());
+    }
+    #[test]
+    fn parses_focus_mode() {
+        let parse = |v: &str| {
+            Config::parse(&format!("focus_mode = {v}"))
+                .unwrap()
+                .focus_mode
+        };
+        assert_eq!(parse("\"click\""), FocusMode::Click);
+        assert_eq!(parse("\"keyboard\""), FocusMode::Keyboard);
+        assert_eq!(parse("\"sloppy\""), FocusMode::default());
+        assert_eq!(parse("click"), FocusMode::default());
+    }
+    #[test]
+    fn parses_warp_pointer() {
+        let parse = |v: &str| {
+            Config::parse(&format!("warp_pointer = {v}"))
+                .unwrap()
+                .warp_pointer
+        };
+        assert!(parse("true"));
+        assert!(!parse("false"));
+        assert!(!parse("yes"));
+    }
+    #[test]
+    fn parses_scratchpads_with_instances() {
+        let c = Config::parse(
+            "scratchpad_2 = [\"kitty\", \"--name\", \"dropdown\"]\nscratchpad_2_instance = \"dropdown\"",
+        )
+        .unwrap();
+        assert_eq!(c.scratchpads[1].command[2], "dropdown");
+        assert_eq!(c.scratchpads[1].instance, "dropdown");
+        assert!(c.scratchpads[0].command.is_empty());
+        assert!(Config::parse("scratchpad_1 = [\"qalculate-gtk\"]").is_err());
+        assert!(Config::parse("scratchpad_1_instance = \"calc\"").is_err());
+        assert!(Config::parse("scratchpad_1 = [\"xterm\"]\nscratchpad_1_class = \"x\"").is_err());
+        assert!(Config::parse("scratchpad_10 = [\"xterm\"]").is_err());
+    }
 }
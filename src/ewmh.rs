@@ -0,0 +1,154 @@
+//! Pure decoding of EWMH client messages and focus requests.
+
+use crate::layout::{Edges, Rect};
+
+/// `_NET_WM_MOVERESIZE` direction that cancels an interaction.
+pub const MOVERESIZE_CANCEL: u32 = 11;
+
+/// Edges dragged by a pointer-driven `_NET_WM_MOVERESIZE` direction; keyboard
+/// directions, cancel, and unknown values give `None`.
+pub fn moveresize_edges(direction: u32) -> Option<Edges> {
+    let (left, right, top, bottom) = match direction {
+        0 => (true, false, true, false),
+        1 => (false, false, true, false),
+        2 => (false, true, true, false),
+        3 => (false, true, false, false),
+        4 => (false, true, false, true),
+        5 => (false, false, false, true),
+        6 => (true, false, false, true),
+        7 => (true, false, false, false),
+        8 => return Some(Edges::ALL),
+        _ => return None,
+    };
+    Some(Edges {
+        left,
+        right,
+        top,
+        bottom,
+    })
+}
+
+/// `r` updated with the fields a `_NET_MOVERESIZE_WINDOW` message sets;
+/// gravity is treated as static.
+pub fn moveresize_window(r: Rect, data: [u32; 5]) -> Rect {
+    let flags = data[0];
+    let set = |bit: u32| flags & (1 << bit) != 0;
+    Rect {
+        x: if set(8) { data[1] as i32 } else { r.x },
+        y: if set(9) { data[2] as i32 } else { r.y },
+        width: if set(10) { data[3].max(1) } else { r.width },
+        height: if set(11) { data[4].max(1) } else { r.height },
+    }
+}
+
+/// How much evidence of user intent a mapped or activated window needs
+/// before it may take focus.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FocusStealing {
+    /// Every request is honoured.
+    Off,
+    /// Requests without a timestamp are trusted; stale ones are not.
+    #[default]
+    Smart,
+    /// Only pagers and timestamps newer than the last input are trusted.
+    Strict,
+}
+
+impl FocusStealing {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "off" => Some(Self::Off),
+            "smart" => Some(Self::Smart),
+            "strict" => Some(Self::Strict),
+            _ => None,
+        }
+    }
+}
+
+/// Source indication of a `_NET_ACTIVE_WINDOW` request.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Source {
+    Legacy,
+    Application,
+    Pager,
+}
+
+impl Source {
+    pub fn new(value: u32) -> Self {
+        match value {
+            1 => Self::Application,
+            2 => Self::Pager,
+            _ => Self::Legacy,
+        }
+    }
+}
+
+/// Whether X server time `a` is not earlier than `b`, across wraparound.
+pub fn not_before(a: u32, b: u32) -> bool {
+    a.wrapping_sub(b) as i32 >= 0
+}
+
+/// Whether a request from `source` carrying `user_time` may take focus when
+/// the user's last known input happened at `last_input`. A user time of 0
+/// asks not to be focused at all.
+pub fn may_focus(
+    policy: FocusStealing,
+    source: Source,
+    user_time: Option<u32>,
+    last_input: u32,
+) -> bool {
+    match (policy, source, user_time) {
+        (FocusStealing::Off, _, _) | (_, Source::Pager, _) => true,
+        (_, _, Some(0)) => false,
+        (_, _, Some(t)) => not_before(t, last_input),
+        (FocusStealing::Smart, _, None) => true,
+        (FocusStealing::Strict, _, None) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn moveresize_directions_map_to_edges() {
+        let top_left = moveresize_edges(0).unwrap();
+        assert!(top_left.left && top_left.top && !top_left.right && !top_left.bottom);
+        assert_eq!(moveresize_edges(8), Some(Edges::ALL));
+        assert_eq!(moveresize_edges(9), None);
+        assert_eq!(moveresize_edges(MOVERESIZE_CANCEL), None);
+    }
+    #[test]
+    fn moveresize_window_sets_only_flagged_fields() {
+        let r = Rect {
+            x: 10,
+            y: 20,
+            width: 300,
+            height: 200,
+        };
+        let moved = moveresize_window(r, [1 << 8 | 1 << 11, (-5i32) as u32, 99, 99, 0]);
+        assert_eq!(
+            moved,
+            Rect {
+                x: -5,
+                height: 1,
+                ..r
+            }
+        );
+        assert_eq!(moveresize_window(r, [0, 1, 2, 3, 4]), r);
+    }
+    #[test]
+    fn focus_requests_need_fresh_user_time() {
+        use FocusStealing::*;
+        let app = Source::Application;
+        assert!(may_focus(Off, app, Some(0), 100));
+        assert!(may_focus(Strict, Source::Pager, None, 100));
+        assert!(!may_focus(Smart, app, Some(0), 100));
+        assert!(!may_focus(Smart, app, Some(99), 100));
+        assert!(may_focus(Smart, app, Some(100), 100));
+        assert!(may_focus(Smart, Source::Legacy, None, 100));
+        assert!(!may_focus(Strict, app, None, 100));
+        assert!(may_focus(Strict, app, Some(5), u32::MAX - 5));
+        assert_eq!(FocusStealing::parse("strict"), Some(Strict));
+        assert_eq!(FocusStealing::parse("on"), None);
+    }
+}
@@ -0,0 +1,71 @@
+//! Focus policy and telling the user's pointer crossings from our own.
+
+/// What besides the keyboard moves focus between clients.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FocusMode {
+    /// Focus follows the pointer into a client.
+    #[default]
+    FollowMouse,
+    /// Clicking a client focuses it; the click still reaches the client.
+    Click,
+    /// Only key bindings, tab bars, and activation requests move focus.
+    Keyboard,
+}
+
+impl FocusMode {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "follow_mouse" => Some(Self::FollowMouse),
+            "click" => Some(Self::Click),
+            "keyboard" => Some(Self::Keyboard),
+            _ => None,
+        }
+    }
+}
+
+/// Remembers the request after which events stop being side effects of our
+/// own configure and stacking requests. Events carry the sequence number of
+/// the last request the server processed before sending them.
+#[derive(Default)]
+pub struct Crossings {
+    mark: Option<u16>,
+}
+
+impl Crossings {
+    /// Treat events sent before request `sequence` was processed as ours.
+    pub fn expect(&mut self, sequence: u64) {
+        self.mark = Some(sequence as u16);
+    }
+    /// Whether an event with `sequence` predates the marked request; the
+    /// first later event clears the mark.
+    pub fn ours(&mut self, sequence: u16) -> bool {
+        let Some(mark) = self.mark else {
+            return false;
+        };
+        let before = (sequence.wrapping_sub(mark) as i16) < 0;
+        if !before {
+            self.mark = None;
+        }
+        before
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn parses_focus_modes() {
+        assert_eq!(FocusMode::parse("click"), Some(FocusMode::Click));
+        assert_eq!(FocusMode::parse("sloppy"), None);
+    }
+    #[test]
+    fn crossings_before_the_mark_are_ours() {
+        let mut c = Crossings::default();
+        assert!(!c.ours(10));
+        c.expect(0x1_0005);
+        assert!(c.ours(4));
+        assert!(c.ours(0xfff0));
+        assert!(!c.ours(5));
+        assert!(!c.ours(4));
+    }
+}
@@ -0,0 +1,153 @@
+//! Pure decoding of ICCCM client properties.
+
+/// `WM_STATE` values.
+pub const NORMAL_STATE: u32 = 1;
+pub const ICONIC_STATE: u32 = 3;
+
+/// `WM_HINTS` flags marking the input field and the urgency bit as set.
+const INPUT_HINT: u32 = 1;
+const URGENCY_HINT: u32 = 1 << 8;
+
+/// The parts of a `WM_HINTS` property BoringWM acts on.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct WmHints {
+    /// The input field, when the client set it.
+    pub input: Option<bool>,
+    pub urgent: bool,
+}
+
+impl WmHints {
+    pub fn parse(value: &[u32]) -> Self {
+        let flags = value.first().copied().unwrap_or(0);
+        Self {
+            input: (flags & INPUT_HINT != 0).then(|| value.get(1).is_some_and(|v| *v != 0)),
+            urgent: flags & URGENCY_HINT != 0,
+        }
+    }
+    /// Whether the client relies on the window manager to set input focus;
+    /// clients that leave the field unset are treated as if they do.
+    pub fn accepts_input(self) -> bool {
+        self.input.unwrap_or(true)
+    }
+}
+
+/// A `WM_HINTS` value with the urgency bit cleared, or `None` when it is
+/// not set. Writing it back acknowledges the urgency, so the next update
+/// from the client does not raise it again.
+pub fn without_urgency(value: &[u32]) -> Option<Vec<u32>> {
+    let flags = *value.first()?;
+    (flags & URGENCY_HINT != 0).then(|| {
+        let mut cleared = value.to_vec();
+        cleared[0] = flags & !URGENCY_HINT;
+        cleared
+    })
+}
+
+/// ICCCM 4.1.7 input models, from `WM_HINTS.input` and `WM_TAKE_FOCUS`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FocusModel {
+    NoInput,
+    Passive,
+    LocallyActive,
+    GloballyActive,
+}
+
+impl FocusModel {
+    pub fn new(input: bool, take_focus: bool) -> Self {
+        match (input, take_focus) {
+            (false, false) => Self::NoInput,
+            (true, false) => Self::Passive,
+            (true, true) => Self::LocallyActive,
+            (false, true) => Self::GloballyActive,
+        }
+    }
+    /// Whether the window manager calls `SetInputFocus` on the window.
+    pub fn set_input_focus(self) -> bool {
+        matches!(self, Self::Passive | Self::LocallyActive)
+    }
+    pub fn take_focus(self) -> bool {
+        matches!(self, Self::LocallyActive | Self::GloballyActive)
+    }
+}
+
+/// Instance and class names from a `WM_CLASS` property value.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WmClass {
+    pub instance: String,
+    pub class: String,
+}
+
+impl WmClass {
+    pub fn parse(value: &[u8]) -> Self {
+        let mut parts = value
+            .split(|b| *b == 0)
+            .map(|part| String::from_utf8_lossy(part).into_owned());
+        Self {
+            instance: parts.next().unwrap_or_default(),
+            class: parts.next().unwrap_or_default(),
+        }
+    }
+    /// Whether the instance name equals `name`. The class is shared by
+    /// every window of an application, so it never identifies one window.
+    pub fn matches(&self, name: &str) -> bool {
+        !name.is_empty() && self.instance == name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn wm_class_splits_instance_and_class() {
+        let c = WmClass::parse(b"dropdown\0kitty\0");
+        assert_eq!(c.instance, "dropdown");
+        assert_eq!(c.class, "kitty");
+        assert!(c.matches("dropdown") && !c.matches("kitty"));
+        assert!(!c.matches(""));
+    }
+    #[test]
+    fn windows_match_on_instance_not_class() {
+        assert!(WmClass::parse(b"dropdown\0kitty\0").matches("dropdown"));
+        assert!(!WmClass::parse(b"kitty\0dropdown\0").matches("dropdown"));
+        assert!(!WmClass::parse(b"kitty\0kitty\0").matches("dropdown"));
+    }
+    #[test]
+    fn wm_hints_read_the_urgency_flag() {
+        assert!(WmHints::parse(&[URGENCY_HINT | 1, 1]).urgent);
+        assert!(!WmHints::parse(&[1, 1]).urgent);
+        assert_eq!(WmHints::parse(&[]), WmHints::default());
+    }
+    #[test]
+    fn urgency_is_cleared_from_wm_hints() {
+        assert_eq!(
+            without_urgency(&[URGENCY_HINT | INPUT_HINT, 1, 7]),
+            Some(vec![INPUT_HINT, 1, 7])
+        );
+        assert_eq!(without_urgency(&[INPUT_HINT, 1]), None);
+        assert_eq!(without_urgency(&[]), None);
+    }
+    #[test]
+    fn wm_hints_input_defaults_to_true() {
+        assert!(WmHints::parse(&[]).accepts_input());
+        assert!(WmHints::parse(&[URGENCY_HINT, 0]).accepts_input());
+        assert!(!WmHints::parse(&[INPUT_HINT, 0]).accepts_input());
+        assert!(!WmHints::parse(&[INPUT_HINT]).accepts_input());
+        assert!(WmHints::parse(&[INPUT_HINT, 1]).accepts_input());
+    }
+    #[test]
+    fn focus_models_follow_icccm() {
+        let no_input = FocusModel::new(false, false);
+        assert!(!no_input.set_input_focus() && !no_input.take_focus());
+        assert!(FocusModel::new(true, false).set_input_focus());
+        let local = FocusModel::new(true, true);
+        assert!(local.set_input_focus() && local.take_focus());
+        let global = FocusModel::new(false, true);
+        assert_eq!(global, FocusModel::GloballyActive);
+        assert!(!global.set_input_focus() && global.take_focus());
+    }
+    #[test]
+    fn wm_class_tolerates_truncated_values() {
+        assert_eq!(WmClass::parse(b"").class, "");
+        assert_eq!(WmClass::parse(b"only").instance, "only");
+    }
+}
@@ -3,6 +3,7 @@ use x11rb::{connection::Connection, protocol::xproto::*, rust_connection::RustCo
 
 pub const MOD: ModMask = ModMask::M4;
 pub const SHIFT: ModMask = ModMask::SHIFT;
+pub const CONTROL: ModMask = ModMask::CONTROL;
 pub const KEY_RETURN: u8 = 36;
 pub const KEY_Q: u8 = 24;
 pub const KEY_J: u8 = 44;
@@ -17,6 +18,26 @@ pub const KEY_L: u8 = 46;
 pub const KEY_M: u8 = 58;
 pub const KEY_R: u8 = 27;
 pub const KEY_E: u8 = 26;
+pub const KEY_O: u8 = 32;
+pub const KEY_N: u8 = 57;
+pub const KEY_U: u8 = 30;
+pub const KEY_S: u8 = 39;
+pub const KEY_A: u8 = 38;
+pub const KEY_TAB: u8 = 23;
+pub const KEY_BACKSPACE: u8 = 22;
+pub const KEY_LEFT: u8 = 113;
+pub const KEY_UP: u8 = 111;
+pub const KEY_RIGHT: u8 = 114;
+pub const KEY_DOWN: u8 = 116;
+pub const KEY_MINUS: u8 = 20;
+pub const KEY_EQUAL: u8 = 21;
+pub const KEY_BRACKET_LEFT: u8 = 34;
+pub const KEY_BRACKET_RIGHT: u8 = 35;
+pub const KEY_GRAVE: u8 = 49;
+pub const KEY_COMMA: u8 = 59;
+pub const KEY_PERIOD: u8 = 60;
+/// Super_L and Super_R, whose release ends a Mod+Tab cycle.
+pub const MOD_KEYS: [u8; 2] = [133, 134];
 pub const DIGITS: [u8; 9] = [10, 11, 12, 13, 14, 15, 16, 17, 18];
 
 pub fn normalized(state: KeyButMask) -> ModMask {
@@ -25,28 +46,61 @@ pub fn normalized(state: KeyButMask) -> ModMask {
 
 pub fn grab_keys(conn: &RustConnection, root: Window) -> anyhow::Result<()> {
     let mut bindings = vec![
-        KEY_RETURN, KEY_Q, KEY_J, KEY_K, KEY_T, KEY_B, KEY_D, KEY_F, KEY_SPACE, KEY_H, KEY_L,
-        KEY_M, KEY_R, KEY_E,
+        KEY_RETURN,
+        KEY_Q,
+        KEY_J,
+        KEY_K,
+        KEY_T,
+        KEY_B,
+        KEY_D,
+        KEY_F,
+        KEY_SPACE,
+        KEY_H,
+        KEY_L,
+        KEY_M,
+        KEY_R,
+        KEY_E,
+        KEY_COMMA,
+        KEY_PERIOD,
+        KEY_O,
+        KEY_MINUS,
+        KEY_EQUAL,
+        KEY_BRACKET_LEFT,
+        KEY_BRACKET_RIGHT,
+        KEY_GRAVE,
+        KEY_N,
+        KEY_U,
+        KEY_S,
+        KEY_A,
+        KEY_TAB,
+        KEY_BACKSPACE,
+        KEY_LEFT,
+        KEY_UP,
+        KEY_RIGHT,
+        KEY_DOWN,
     ];
     bindings.extend(DIGITS);
+    let mut grabs = Vec::new();
     for modifiers in [MOD, MOD | SHIFT] {
+        grabs.extend(bindings.iter().map(|key| (modifiers, *key)));
+    }
+    grabs.extend(DIGITS.iter().map(|key| (MOD | CONTROL, *key)));
+    for (modifiers, key) in grabs {
         for ignored in [
             ModMask::default(),
             ModMask::M2,
             ModMask::LOCK,
             ModMask::M2 | ModMask::LOCK,
         ] {
-            for key in &bindings {
-                conn.grab_key(
-                    false,
-                    root,
-                    modifiers | ignored,
-                    *key,
-                    GrabMode::ASYNC,
-                    GrabMode::ASYNC,
-                )
-                .context("failed to request key grab")?;
-            }
+            conn.grab_key(
+                false,
+                root,
+                modifiers | ignored,
+                key,
+                GrabMode::ASYNC,
+                GrabMode::ASYNC,
+            )
+            .context("failed to request key grab")?;
         }
     }
     conn.flush().context("failed to install key grabs")
@@ -0,0 +1,171 @@
+//! Terminating the local processes behind force-killed clients.
+
+use log::warn;
+use std::{
+    fs,
+    process::{Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// How long a process gets between `SIGTERM` and `SIGKILL`.
+pub const GRACE: Duration = Duration::from_secs(3);
+
+/// Whether a `WM_CLIENT_MACHINE` value names `host`. Either side may be
+/// fully qualified; only the first label is compared then.
+pub fn same_host(machine: &str, host: &str) -> bool {
+    let short = |name: &str| {
+        name.split('.')
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase()
+    };
+    if machine.is_empty() || host.is_empty() {
+        return false;
+    }
+    machine.eq_ignore_ascii_case(host)
+        || ((machine.contains('.') != host.contains('.')) && short(machine) == short(host))
+}
+
+/// A `_NET_WM_PID` value that is safe to signal: never a process group,
+/// init, or BoringWM itself.
+pub fn target(pid: u32, own: u32) -> Option<i32> {
+    (pid > 1 && pid != own)
+        .then_some(pid)
+        .and_then(|p| i32::try_from(p).ok())
+}
+
+pub fn local_hostname() -> Option<String> {
+    fs::read_to_string("/proc/sys/kernel/hostname")
+        .ok()
+        .map(|h| h.trim().to_owned())
+        .filter(|h| !h.is_empty())
+}
+
+/// Start time of `pid` in clock ticks since boot, from `/proc/<pid>/stat`.
+/// Together with the pid it names one process even after pid reuse.
+pub fn start_time(pid: i32) -> Option<u64> {
+    parse_start_time(&fs::read_to_string(format!("/proc/{pid}/stat")).ok()?)
+}
+
+/// Field 22 of a `/proc/<pid>/stat` line. The command name in field 2 may
+/// contain spaces and parentheses, so fields are counted after the last `)`.
+fn parse_start_time(stat: &str) -> Option<u64> {
+    let (_, rest) = stat.rsplit_once(')')?;
+    rest.split_whitespace().nth(19)?.parse().ok()
+}
+
+/// Send `signal` (a name such as `TERM`) to `pid` with kill(1), ignoring
+/// processes that already exited. The event loop does not wait for kill(1);
+/// a thread reaps it.
+pub fn signal(pid: i32, signal: &str) {
+    let child = Command::new("kill")
+        .args(["-s", signal, &pid.to_string()])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn();
+    match child {
+        Ok(mut child) => {
+            thread::spawn(move || child.wait());
+        }
+        Err(e) => warn!("cannot run kill for {pid}: {e}"),
+    }
+}
+
+/// A process sent `SIGTERM`, named by its pid and start time.
+struct Pending {
+    pid: i32,
+    start: u64,
+    deadline: Instant,
+}
+
+/// Processes sent `SIGTERM` that get `SIGKILL` once their grace runs out.
+/// Destroying their windows does not cancel it: `KillClient` destroys them
+/// right away, while a hung process may live on.
+#[derive(Default)]
+pub struct Kills {
+    pending: Vec<Pending>,
+}
+
+impl Kills {
+    /// Schedule `SIGKILL` for `pid`, started at `start`.
+    pub fn schedule(&mut self, pid: i32, start: u64, now: Instant) {
+        if !self
+            .pending
+            .iter()
+            .any(|p| p.pid == pid && p.start == start)
+        {
+            self.pending.push(Pending {
+                pid,
+                start,
+                deadline: now + GRACE,
+            });
+        }
+    }
+    /// Processes whose grace ended by `now`, with their start times,
+    /// removed from the schedule.
+    pub fn due(&mut self, now: Instant) -> Vec<(i32, u64)> {
+        let (due, rest) = std::mem::take(&mut self.pending)
+            .into_iter()
+            .partition::<Vec<_>, _>(|p| p.deadline <= now);
+        self.pending = rest;
+        due.into_iter().map(|p| (p.pid, p.start)).collect()
+    }
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.pending.iter().map(|p| p.deadline).min()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn hosts_match_by_name_or_short_name() {
+        assert!(same_host("box", "box"));
+        assert!(same_host("Box.example.org", "box"));
+        assert!(same_host("box", "box.example.org"));
+        assert!(!same_host("box.example.org", "box.example.com"));
+        assert!(!same_host("other", "box"));
+        assert!(!same_host("", "box"));
+    }
+    #[test]
+    fn unsafe_pids_are_rejected() {
+        assert_eq!(target(0, 10), None);
+        assert_eq!(target(1, 10), None);
+        assert_eq!(target(10, 10), None);
+        assert_eq!(target(u32::MAX, 10), None);
+        assert_eq!(target(42, 10), Some(42));
+    }
+    #[test]
+    fn start_time_skips_the_command_name() {
+        let stat = "42 (a) b (c)) S 1 42 42 0 -1 4194560 0 0 0 0 0 0 0 0 20 0 1 0 123456 0 0";
+        assert_eq!(parse_start_time(stat), Some(123456));
+        assert_eq!(parse_start_time("42 (short) S 1"), None);
+        assert_eq!(parse_start_time(""), None);
+    }
+    #[test]
+    fn kills_fire_after_grace() {
+        let mut k = Kills::default();
+        let now = Instant::now();
+        k.schedule(42, 7, now);
+        k.schedule(42, 7, now + GRACE);
+        k.schedule(43, 8, now + Duration::from_secs(1));
+        assert_eq!(k.next_deadline(), Some(now + GRACE));
+        assert!(k.due(now).is_empty());
+        assert_eq!(k.due(now + GRACE), vec![(42, 7)]);
+        assert_eq!(k.due(now + GRACE * 2), vec![(43, 8)]);
+        assert_eq!(k.next_deadline(), None);
+    }
+    #[test]
+    fn kills_survive_window_destruction() {
+        // Only the pid and its start time name a kill, so nothing about the
+        // windows of the process, destroyed by KillClient, can drop it.
+        let mut k = Kills::default();
+        let now = Instant::now();
+        k.schedule(42, 7, now);
+        k.schedule(42, 9, now);
+        assert!(k.due(now + GRACE / 2).is_empty());
+        assert_eq!(k.due(now + GRACE), vec![(42, 7), (42, 9)]);
+    }
+}
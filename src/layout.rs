@@ -1,4 +1,4 @@
-//! Pure master/stack geometry calculations.
+//! Pure tiling geometry calculations.
 
 #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 pub struct Rect {
@@ -15,29 +15,340 @@ impl Rect {
             && x < self.x.saturating_add(self.width as i32)
             && y < self.y.saturating_add(self.height as i32)
     }
+    pub fn center(self) -> (i32, i32) {
+        (
+            self.x.saturating_add((self.width / 2) as i32),
+            self.y.saturating_add((self.height / 2) as i32),
+        )
+    }
 }
 
-/// Calculate deterministic master/stack rectangles inside a monitor work area.
-/// Remainder pixels are assigned to the first stack clients.
-pub fn master_stack(area: Rect, count: usize, gap: u32, border: u32, ratio: f32) -> Vec<Rect> {
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Layout {
+    #[default]
+    MasterStack,
+    Spiral,
+    Dwindle,
+    Tabbed,
+    Stacked,
+}
+
+impl Layout {
+    /// The layout selected by the next cycle step.
+    pub fn next(self) -> Self {
+        match self {
+            Self::MasterStack => Self::Spiral,
+            Self::Spiral => Self::Dwindle,
+            Self::Dwindle => Self::Tabbed,
+            Self::Tabbed => Self::Stacked,
+            Self::Stacked => Self::MasterStack,
+        }
+    }
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "master_stack" => Some(Self::MasterStack),
+            "spiral" => Some(Self::Spiral),
+            "dwindle" => Some(Self::Dwindle),
+            "tabbed" => Some(Self::Tabbed),
+            "stacked" => Some(Self::Stacked),
+            _ => None,
+        }
+    }
+}
+
+pub const MAX_NMASTER: usize = 9;
+
+/// Space around the tiled area (`outer`) and between tiled clients (`inner`).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Gaps {
+    pub inner: u32,
+    pub outer: u32,
+}
+
+impl Gaps {
+    pub fn uniform(gap: u32) -> Self {
+        Self {
+            inner: gap,
+            outer: gap,
+        }
+    }
+}
+
+pub const MAX_GAP: u32 = 100;
+pub const MAX_BORDER: u32 = 20;
+
+/// Layout selection, master parameters, gaps, and border of one workspace.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Tiling {
+    pub layout: Layout,
+    pub master_ratio: f32,
+    pub nmaster: usize,
+    pub gaps: Gaps,
+    pub gaps_enabled: bool,
+    pub border_width: u32,
+}
+
+impl Default for Tiling {
+    fn default() -> Self {
+        Self {
+            layout: Layout::default(),
+            master_ratio: 0.6,
+            nmaster: 1,
+            gaps: Gaps::uniform(8),
+            gaps_enabled: true,
+            border_width: 2,
+        }
+    }
+}
+
+impl Tiling {
+    /// Rectangles for clients with the given size weights, in tiling order.
+    pub fn rects(&self, area: Rect, weights: &[f32], gaps: Gaps, border: u32) -> Vec<Rect> {
+        match self.layout {
+            Layout::MasterStack => {
+                master_stack(area, weights, self.nmaster, gaps, border, self.master_ratio)
+            }
+            Layout::Spiral => spiral(area, weights.len(), gaps, border),
+            Layout::Dwindle => dwindle(area, weights.len(), gaps, border),
+            Layout::Tabbed | Layout::Stacked => {
+                let bar = self.tab_bar(area, weights.len(), gaps.outer);
+                container(area, weights.len(), gaps.outer, border, bar)
+            }
+        }
+    }
+    /// Title strip above a tabbed or stacked container, if there is room.
+    pub fn tab_bar(&self, area: Rect, count: usize, gap: u32) -> Option<Rect> {
+        let rows = match self.layout {
+            Layout::Tabbed => 1,
+            Layout::Stacked => count as u32,
+            _ => return None,
+        };
+        if count == 0 || area.width == 0 || area.height == 0 {
+            return None;
+        }
+        let outer = inset(area, gap);
+        let height = rows.saturating_mul(TAB_HEIGHT).min(outer.height / 2);
+        (height > 0).then_some(Rect { height, ..outer })
+    }
+}
+
+/// Height of one title row in tabbed and stacked layouts.
+pub const TAB_HEIGHT: u32 = 18;
+
+/// Title rectangles inside a tab bar: side by side when tabbed, one row per
+/// client when stacked.
+pub fn tabs(bar: Rect, count: usize, stacked: bool) -> Vec<Rect> {
+    let weights = vec![1.0; count];
+    let mut result = Vec::with_capacity(count);
+    let (mut x, mut y) = (bar.x, bar.y);
+    if stacked {
+        for height in distribute(bar.height.max(count as u32), &weights) {
+            result.push(Rect { y, height, ..bar });
+            y = y.saturating_add(height as i32);
+        }
+    } else {
+        for width in distribute(bar.width.max(count as u32), &weights) {
+            result.push(Rect { x, width, ..bar });
+            x = x.saturating_add(width as i32);
+        }
+    }
+    result
+}
+
+/// Every client of a tabbed or stacked container shares the area below the bar.
+fn container(area: Rect, count: usize, gap: u32, border: u32, bar: Option<Rect>) -> Vec<Rect> {
     if count == 0 || area.width == 0 || area.height == 0 {
         return Vec::new();
     }
+    let outer = inset(area, gap);
+    let bar_height = bar.map_or(0, |b| b.height);
+    let client = Rect {
+        x: outer.x,
+        y: outer.y.saturating_add(bar_height as i32),
+        width: outer.width.saturating_sub(border.saturating_mul(2)).max(1),
+        height: outer
+            .height
+            .saturating_sub(bar_height)
+            .saturating_sub(border.saturating_mul(2))
+            .max(1),
+    };
+    vec![client; count]
+}
+
+/// Shrink `area` by a gap that always leaves at least one pixel.
+fn inset(area: Rect, gap: u32) -> Rect {
     let gap = gap
         .min(area.width.saturating_sub(1) / 2)
         .min(area.height.saturating_sub(1) / 2);
-    let inner = |outer: u32| outer.saturating_sub(border.saturating_mul(2)).max(1);
-    let x = area.x.saturating_add(gap as i32);
-    let y = area.y.saturating_add(gap as i32);
-    let width = area.width.saturating_sub(gap.saturating_mul(2)).max(1);
-    let height = area.height.saturating_sub(gap.saturating_mul(2)).max(1);
-    if count == 1 {
-        return vec![Rect {
-            x,
-            y,
-            width: inner(width),
-            height: inner(height),
-        }];
+    Rect {
+        x: area.x.saturating_add(gap as i32),
+        y: area.y.saturating_add(gap as i32),
+        width: area.width.saturating_sub(gap.saturating_mul(2)).max(1),
+        height: area.height.saturating_sub(gap.saturating_mul(2)).max(1),
+    }
+}
+
+/// `r` stretched across `area` horizontally and/or vertically, leaving room
+/// for `border` on both sides.
+pub fn maximized(area: Rect, r: Rect, horizontal: bool, vertical: bool, border: u32) -> Rect {
+    let inner = |length: u32| length.saturating_sub(border.saturating_mul(2)).max(1);
+    let mut out = r;
+    if horizontal {
+        out.x = area.x;
+        out.width = inner(area.width);
+    }
+    if vertical {
+        out.y = area.y;
+        out.height = inner(area.height);
+    }
+    out
+}
+
+/// Window edges following the pointer during an interactive move or resize.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Edges {
+    pub left: bool,
+    pub right: bool,
+    pub top: bool,
+    pub bottom: bool,
+}
+
+impl Edges {
+    /// Every edge: the window moves without changing size.
+    pub const ALL: Self = Self {
+        left: true,
+        right: true,
+        top: true,
+        bottom: true,
+    };
+}
+
+/// `r` with `edges` dragged by `dx`, `dy`, keeping at least one pixel.
+pub fn drag(r: Rect, edges: Edges, dx: i32, dy: i32) -> Rect {
+    let span = |start: i32, length: u32, low: bool, high: bool, delta: i32| {
+        let mut a = start;
+        let mut b = start.saturating_add(length as i32);
+        if low {
+            a = a.saturating_add(delta);
+        }
+        if high {
+            b = b.saturating_add(delta);
+        }
+        if low && !high {
+            a = a.min(b - 1);
+        } else if high && !low {
+            b = b.max(a + 1);
+        }
+        (a, b.saturating_sub(a).max(1) as u32)
+    };
+    let (x, width) = span(r.x, r.width, edges.left, edges.right, dx);
+    let (y, height) = span(r.y, r.height, edges.top, edges.bottom, dy);
+    Rect {
+        x,
+        y,
+        width,
+        height,
+    }
+}
+
+/// A `width` x `height` rectangle centered in `area` and clamped to it.
+pub fn centered(area: Rect, width: u32, height: u32) -> Rect {
+    let width = width.clamp(1, area.width.max(1));
+    let height = height.clamp(1, area.height.max(1));
+    Rect {
+        x: area
+            .x
+            .saturating_add(((area.width - width.min(area.width)) / 2) as i32),
+        y: area
+            .y
+            .saturating_add(((area.height - height.min(area.height)) / 2) as i32),
+        width,
+        height,
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+/// Index of the rectangle in `candidates` nearest to `from` in `direction`.
+/// A candidate qualifies when its center lies past that edge of `from`;
+/// ones overlapping `from` across the direction win, then the smallest gap,
+/// then the smallest sideways offset between centers.
+pub fn neighbour(from: Rect, candidates: &[Rect], direction: Direction) -> Option<usize> {
+    let (fx, fy) = from.center();
+    let right = |r: Rect| i64::from(r.x) + i64::from(r.width);
+    let bottom = |r: Rect| i64::from(r.y) + i64::from(r.height);
+    let overlaps = |a0: i64, a1: i64, b0: i64, b1: i64| a0 < b1 && b0 < a1;
+    candidates
+        .iter()
+        .enumerate()
+        .filter_map(|(i, c)| {
+            let (cx, cy) = c.center();
+            let (cx, cy, fx, fy) = (i64::from(cx), i64::from(cy), i64::from(fx), i64::from(fy));
+            let (past, gap, side, overlap) = match direction {
+                Direction::Left => (
+                    cx < i64::from(from.x),
+                    i64::from(from.x) - right(*c),
+                    (cy - fy).abs(),
+                    overlaps(c.y.into(), bottom(*c), from.y.into(), bottom(from)),
+                ),
+                Direction::Right => (
+                    cx >= right(from),
+                    i64::from(c.x) - right(from),
+                    (cy - fy).abs(),
+                    overlaps(c.y.into(), bottom(*c), from.y.into(), bottom(from)),
+                ),
+                Direction::Up => (
+                    cy < i64::from(from.y),
+                    i64::from(from.y) - bottom(*c),
+                    (cx - fx).abs(),
+                    overlaps(c.x.into(), right(*c), from.x.into(), right(from)),
+                ),
+                Direction::Down => (
+                    cy >= bottom(from),
+                    i64::from(c.y) - bottom(from),
+                    (cx - fx).abs(),
+                    overlaps(c.x.into(), right(*c), from.x.into(), right(from)),
+                ),
+            };
+            past.then_some((i, (!overlap, gap.max(0), side)))
+        })
+        .min_by_key(|(_, key)| *key)
+        .map(|(i, _)| i)
+}
+
+/// Calculate deterministic master/stack rectangles inside a monitor work area.
+/// The first `nmaster` clients share the master column; with no master or no
+/// stack clients the remaining column spans the full width. Each column is
+/// divided in proportion to the client weights and remainder pixels are
+/// assigned to the first clients of each column.
+pub fn master_stack(
+    area: Rect,
+    weights: &[f32],
+    nmaster: usize,
+    gaps: Gaps,
+    border: u32,
+    ratio: f32,
+) -> Vec<Rect> {
+    let count = weights.len();
+    if count == 0 || area.width == 0 || area.height == 0 {
+        return Vec::new();
+    }
+    let Rect {
+        x,
+        y,
+        width,
+        height,
+    } = inset(area, gaps.outer);
+    let gap = gaps.inner;
+    let masters = nmaster.min(count);
+    if masters == 0 || masters == count {
+        return column(x, y, width, height, weights, gap, border);
     }
 
     let ratio = ratio.clamp(0.2, 0.8);
@@ -45,36 +356,182 @@ pub fn master_stack(area: Rect, count: usize, gap: u32, border: u32, ratio: f32)
     let columns = width.saturating_sub(column_gap).max(2);
     let master_width = ((columns as f32 * ratio).round() as u32).clamp(1, columns - 1);
     let stack_width = columns - master_width;
-    let mut result = vec![Rect {
-        x,
+    let mut result = column(x, y, master_width, height, &weights[..masters], gap, border);
+    result.extend(column(
+        x.saturating_add(master_width as i32)
+            .saturating_add(column_gap as i32),
         y,
-        width: inner(master_width),
-        height: inner(height),
-    }];
-    let stack_count = (count - 1) as u32;
+        stack_width,
+        height,
+        &weights[masters..],
+        gap,
+        border,
+    ));
+    result
+}
+
+/// Stack clients vertically in one column separated by `gap`.
+fn column(
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+    weights: &[f32],
+    gap: u32,
+    border: u32,
+) -> Vec<Rect> {
+    let inner = |outer: u32| outer.saturating_sub(border.saturating_mul(2)).max(1);
+    let count = weights.len() as u32;
     let gaps = gap
-        .saturating_mul(stack_count.saturating_sub(1))
-        .min(height.saturating_sub(stack_count));
-    let available = height.saturating_sub(gaps).max(stack_count);
-    let base = available / stack_count;
-    let remainder = available % stack_count;
-    let mut stack_y = y;
-    for index in 0..stack_count {
-        let outer_height = base + u32::from(index < remainder);
+        .saturating_mul(count.saturating_sub(1))
+        .min(height.saturating_sub(count));
+    let available = height.saturating_sub(gaps).max(count);
+    let mut result = Vec::with_capacity(weights.len());
+    let mut row_y = y;
+    for outer_height in distribute(available, weights) {
         result.push(Rect {
-            x: x.saturating_add(master_width as i32)
-                .saturating_add(column_gap as i32),
-            y: stack_y,
-            width: inner(stack_width),
+            x,
+            y: row_y,
+            width: inner(width),
             height: inner(outer_height),
         });
-        stack_y = stack_y
+        row_y = row_y
             .saturating_add(outer_height as i32)
             .saturating_add(gap as i32);
     }
     result
 }
 
+/// Divide `total` pixels in proportion to `weights` so the parts sum to
+/// `total` whenever every part can be at least one pixel. Invalid weights
+/// count as 1.
+fn distribute(total: u32, weights: &[f32]) -> Vec<u32> {
+    if weights.is_empty() {
+        return Vec::new();
+    }
+    let weights = weights
+        .iter()
+        .map(|w| if w.is_finite() && *w > 0.0 { *w } else { 1.0 })
+        .collect::<Vec<_>>();
+    let sum: f32 = weights.iter().sum();
+    let mut parts = weights
+        .iter()
+        .map(|w| ((total as f32 * w / sum).floor() as u32).max(1))
+        .collect::<Vec<_>>();
+    let mut used: u32 = parts.iter().sum();
+    let count = parts.len();
+    let mut index = 0;
+    while used < total {
+        parts[index % count] += 1;
+        used += 1;
+        index += 1;
+    }
+    while used > total {
+        let Some(largest) = (0..parts.len())
+            .filter(|i| parts[*i] > 1)
+            .max_by_key(|i| (parts[*i], std::cmp::Reverse(*i)))
+        else {
+            break;
+        };
+        parts[largest] -= 1;
+        used -= 1;
+    }
+    parts
+}
+
+/// Recursive binary split whose remaining area rotates clockwise inward.
+pub fn spiral(area: Rect, count: usize, gaps: Gaps, border: u32) -> Vec<Rect> {
+    binary_split(area, count, gaps, border, true)
+}
+
+/// Recursive binary split whose remaining area always shrinks toward the
+/// bottom-right corner.
+pub fn dwindle(area: Rect, count: usize, gaps: Gaps, border: u32) -> Vec<Rect> {
+    binary_split(area, count, gaps, border, false)
+}
+
+fn binary_split(area: Rect, count: usize, gaps: Gaps, border: u32, spiral: bool) -> Vec<Rect> {
+    if count == 0 || area.width == 0 || area.height == 0 {
+        return Vec::new();
+    }
+    let inner = |outer: Rect| Rect {
+        width: outer.width.saturating_sub(border.saturating_mul(2)).max(1),
+        height: outer.height.saturating_sub(border.saturating_mul(2)).max(1),
+        ..outer
+    };
+    let bounds = inset(area, gaps.outer);
+    let mut rest = bounds;
+    let mut result = Vec::with_capacity(count);
+    for index in 0..count - 1 {
+        let vertical = index % 2 == 0;
+        let length = if vertical { rest.width } else { rest.height };
+        let (first, split_gap, second) = split(length, gaps.inner);
+        let offset = (first + split_gap) as i32;
+        let (head, tail) = if vertical {
+            (
+                Rect {
+                    width: first,
+                    ..rest
+                },
+                Rect {
+                    x: rest.x.saturating_add(offset),
+                    width: second,
+                    ..rest
+                },
+            )
+        } else {
+            (
+                Rect {
+                    height: first,
+                    ..rest
+                },
+                Rect {
+                    y: rest.y.saturating_add(offset),
+                    height: second,
+                    ..rest
+                },
+            )
+        };
+        // Once `rest` is a single pixel wide the tail has no room left and
+        // would start past the area; overlap the last pixel instead.
+        let tail = clamp_to(tail, bounds);
+        // The spiral hands the far half to every third and fourth client so
+        // the remaining area turns back toward the top-left.
+        let (client, remaining) = if spiral && index % 4 >= 2 {
+            (tail, head)
+        } else {
+            (head, tail)
+        };
+        result.push(inner(client));
+        rest = remaining;
+    }
+    result.push(inner(rest));
+    result
+}
+
+/// `r` moved and shrunk to lie within `bounds`, keeping at least one pixel.
+fn clamp_to(r: Rect, bounds: Rect) -> Rect {
+    let right = bounds.x.saturating_add(bounds.width as i32);
+    let bottom = bounds.y.saturating_add(bounds.height as i32);
+    let x = r.x.clamp(bounds.x, right - 1);
+    let y = r.y.clamp(bounds.y, bottom - 1);
+    Rect {
+        x,
+        y,
+        width: r.width.min((right - x) as u32).max(1),
+        height: r.height.min((bottom - y) as u32).max(1),
+    }
+}
+
+/// Split `length` into two nonzero parts separated by at most `gap` pixels.
+/// The first part receives the odd pixel.
+fn split(length: u32, gap: u32) -> (u32, u32, u32) {
+    let gap = gap.min(length.saturating_sub(2));
+    let available = length.saturating_sub(gap).max(2);
+    let second = available / 2;
+    (available - second, gap, second)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -90,12 +547,12 @@ mod tests {
 
     #[test]
     fn zero_clients() {
-        assert!(master_stack(area(), 0, 8, 2, 0.6).is_empty());
+        assert!(master_stack(area(), &[1.0; 0], 1, Gaps::uniform(8), 2, 0.6).is_empty());
     }
     #[test]
     fn one_client() {
         assert_eq!(
-            master_stack(area(), 1, 8, 2, 0.6),
+            master_stack(area(), &[1.0; 1], 1, Gaps::uniform(8), 2, 0.6),
             vec![Rect {
                 x: 18,
                 y: 28,
@@ -107,12 +564,15 @@ mod tests {
     #[test]
     fn expected_counts() {
         for n in [2, 3, 10] {
-            assert_eq!(master_stack(area(), n, 8, 2, 0.6).len(), n);
+            assert_eq!(
+                master_stack(area(), &vec![1.0; n], 1, Gaps::uniform(8), 2, 0.6).len(),
+                n
+            );
         }
     }
     #[test]
     fn monitor_offset_is_preserved() {
-        assert!(master_stack(area(), 3, 8, 2, 0.6)
+        assert!(master_stack(area(), &[1.0; 3], 1, Gaps::uniform(8), 2, 0.6)
             .iter()
             .all(|r| r.x >= 10 && r.y >= 20));
     }
@@ -132,25 +592,25 @@ mod tests {
                 height: 3,
             },
         ] {
-            assert!(master_stack(a, 10, 8, 2, 0.6)
+            assert!(master_stack(a, &[1.0; 10], 1, Gaps::uniform(8), 2, 0.6)
                 .iter()
                 .all(|r| r.width > 0 && r.height > 0));
         }
     }
     #[test]
     fn gaps_and_borders_do_not_overlap_columns() {
-        let r = master_stack(area(), 3, 12, 4, 0.6);
+        let r = master_stack(area(), &[1.0; 3], 1, Gaps::uniform(12), 4, 0.6);
         assert!(r[0].x + r[0].width as i32 + 8 < r[1].x);
     }
     #[test]
     fn ratio_is_clamped() {
         assert_eq!(
-            master_stack(area(), 2, 8, 2, -5.0),
-            master_stack(area(), 2, 8, 2, 0.2)
+            master_stack(area(), &[1.0; 2], 1, Gaps::uniform(8), 2, -5.0),
+            master_stack(area(), &[1.0; 2], 1, Gaps::uniform(8), 2, 0.2)
         );
         assert_eq!(
-            master_stack(area(), 2, 8, 2, 5.0),
-            master_stack(area(), 2, 8, 2, 0.8)
+            master_stack(area(), &[1.0; 2], 1, Gaps::uniform(8), 2, 5.0),
+            master_stack(area(), &[1.0; 2], 1, Gaps::uniform(8), 2, 0.8)
         );
     }
     #[test]
@@ -162,12 +622,335 @@ mod tests {
                 width: 801,
                 height: 603,
             },
-            10,
-            7,
+            &[1.0; 10],
+            1,
+            Gaps::uniform(7),
             1,
             0.6,
         );
         let last = r.last().unwrap();
         assert!(last.y + last.height as i32 + 2 <= 603);
     }
+    fn disjoint(a: Rect, b: Rect, border: u32) -> bool {
+        let b2 = (border * 2) as i32;
+        a.x + a.width as i32 + b2 <= b.x
+            || b.x + b.width as i32 + b2 <= a.x
+            || a.y + a.height as i32 + b2 <= b.y
+            || b.y + b.height as i32 + b2 <= a.y
+    }
+    #[test]
+    fn binary_splits_have_expected_counts() {
+        for n in [0, 1, 2, 5, 12] {
+            assert_eq!(spiral(area(), n, Gaps::uniform(8), 2).len(), n);
+            assert_eq!(dwindle(area(), n, Gaps::uniform(8), 2).len(), n);
+        }
+    }
+    #[test]
+    fn binary_splits_single_client_matches_master_stack() {
+        assert_eq!(
+            spiral(area(), 1, Gaps::uniform(8), 2),
+            master_stack(area(), &[1.0; 1], 1, Gaps::uniform(8), 2, 0.6)
+        );
+        assert_eq!(
+            dwindle(area(), 1, Gaps::uniform(8), 2),
+            master_stack(area(), &[1.0; 1], 1, Gaps::uniform(8), 2, 0.6)
+        );
+    }
+    #[test]
+    fn binary_splits_are_nonzero_on_tiny_screens() {
+        for a in [
+            Rect {
+                x: 0,
+                y: 0,
+                width: 20,
+                height: 200,
+            },
+            Rect {
+                x: 0,
+                y: 0,
+                width: 3,
+                height: 3,
+            },
+        ] {
+            for r in spiral(a, 10, Gaps::uniform(8), 2)
+                .into_iter()
+                .chain(dwindle(a, 10, Gaps::uniform(8), 2))
+            {
+                assert!(r.width > 0 && r.height > 0);
+            }
+        }
+    }
+    #[test]
+    fn binary_splits_stay_inside_the_area() {
+        for (width, height) in [(3, 3), (1, 1), (2, 7), (20, 200), (1920, 1080)] {
+            let a = Rect {
+                x: 5,
+                y: 9,
+                width,
+                height,
+            };
+            for r in spiral(a, 12, Gaps::uniform(8), 2)
+                .into_iter()
+                .chain(dwindle(a, 12, Gaps::uniform(8), 2))
+            {
+                assert!(
+                    r.x >= a.x
+                        && r.y >= a.y
+                        && r.x + r.width as i32 <= a.x + a.width as i32
+                        && r.y + r.height as i32 <= a.y + a.height as i32,
+                    "{r:?} leaves {a:?}"
+                );
+            }
+        }
+    }
+    #[test]
+    fn binary_splits_do_not_overlap_across_gaps() {
+        for rects in [
+            spiral(area(), 7, Gaps::uniform(12), 4),
+            dwindle(area(), 7, Gaps::uniform(12), 4),
+        ] {
+            for (i, a) in rects.iter().enumerate() {
+                for b in &rects[i + 1..] {
+                    assert!(disjoint(*a, *b, 4), "{a:?} overlaps {b:?}");
+                }
+            }
+        }
+    }
+    #[test]
+    fn binary_splits_preserve_monitor_offset() {
+        let a = area();
+        for r in
+            spiral(a, 6, Gaps::uniform(8), 2)
+                .into_iter()
+                .chain(dwindle(a, 6, Gaps::uniform(8), 2))
+        {
+            assert!(r.x >= a.x && r.y >= a.y);
+            assert!(r.x + r.width as i32 <= a.x + a.width as i32);
+            assert!(r.y + r.height as i32 <= a.y + a.height as i32);
+        }
+    }
+    #[test]
+    fn spiral_turns_while_dwindle_shrinks_toward_corner() {
+        let s = spiral(area(), 4, Gaps::uniform(8), 2);
+        let d = dwindle(area(), 4, Gaps::uniform(8), 2);
+        assert_eq!(s[..2], d[..2]);
+        assert!(s[2].x > s[3].x);
+        assert!(d[3].x > d[2].x);
+    }
+    #[test]
+    fn tiling_dispatches_to_selected_layout() {
+        let mut t = Tiling {
+            nmaster: 2,
+            ..Tiling::default()
+        };
+        assert_eq!(
+            t.rects(area(), &[1.0; 3], Gaps::uniform(8), 2),
+            master_stack(area(), &[1.0; 3], 2, Gaps::uniform(8), 2, 0.6)
+        );
+        t.layout = Layout::Spiral;
+        assert_eq!(
+            t.rects(area(), &[1.0; 3], Gaps::uniform(8), 2),
+            spiral(area(), 3, Gaps::uniform(8), 2)
+        );
+        assert_eq!(Layout::parse("dwindle"), Some(Layout::Dwindle));
+        assert_eq!(Layout::parse("tile"), None);
+    }
+    #[test]
+    fn layouts_cycle_back_to_master_stack() {
+        let mut layout = Layout::default();
+        for _ in 0..5 {
+            layout = layout.next();
+        }
+        assert_eq!(layout, Layout::MasterStack);
+    }
+    #[test]
+    fn several_masters_share_the_master_column() {
+        let r = master_stack(area(), &[1.0; 4], 2, Gaps::uniform(8), 2, 0.6);
+        assert_eq!(r[0].x, r[1].x);
+        assert!(r[0].y + r[0].height as i32 + 4 < r[1].y);
+        assert!(r[1].x + r[1].width as i32 + 4 < r[2].x);
+        assert_eq!(r[2].x, r[3].x);
+    }
+    #[test]
+    fn empty_master_or_stack_uses_full_width() {
+        for nmaster in [0, 3, 5] {
+            let r = master_stack(area(), &[1.0; 3], nmaster, Gaps::uniform(8), 2, 0.6);
+            assert!(r.iter().all(|c| c.x == 18 && c.width == 1900));
+        }
+    }
+    #[test]
+    fn weights_scale_stack_clients_and_fill_column() {
+        let r = master_stack(area(), &[1.0, 1.0, 2.0, 1.0], 1, Gaps::uniform(8), 2, 0.6);
+        assert!(r[2].height > r[1].height + r[1].height / 2);
+        assert_eq!(r[1].height, r[3].height);
+        let last = r.last().unwrap();
+        assert_eq!(last.y + last.height as i32 + 4, 20 + 1080 - 8);
+    }
+    #[test]
+    fn distribution_is_exact_and_tolerates_bad_weights() {
+        assert_eq!(distribute(10, &[1.0, 1.0, 1.0]), vec![4, 3, 3]);
+        assert_eq!(distribute(9, &[2.0, 1.0]), vec![6, 3]);
+        assert_eq!(distribute(6, &[f32::NAN, -1.0, 1.0]), vec![2, 2, 2]);
+        assert_eq!(distribute(3, &[100.0, 0.01, 0.01]).iter().sum::<u32>(), 3);
+    }
+    #[test]
+    fn tabbed_clients_share_the_area_below_the_bar() {
+        let t = Tiling {
+            layout: Layout::Tabbed,
+            ..Tiling::default()
+        };
+        let bar = t.tab_bar(area(), 3, 8).unwrap();
+        assert_eq!(
+            (bar.x, bar.y, bar.width, bar.height),
+            (18, 28, 1904, TAB_HEIGHT)
+        );
+        let r = t.rects(area(), &[1.0; 3], Gaps::uniform(8), 2);
+        assert!(r.iter().all(|c| *c == r[0]));
+        assert_eq!(r[0].y, bar.y + bar.height as i32);
+        assert_eq!(r[0].y + r[0].height as i32 + 4, 20 + 1080 - 8);
+    }
+    #[test]
+    fn stacked_bar_grows_per_client_but_keeps_room() {
+        let t = Tiling {
+            layout: Layout::Stacked,
+            ..Tiling::default()
+        };
+        assert_eq!(t.tab_bar(area(), 3, 8).unwrap().height, 3 * TAB_HEIGHT);
+        assert_eq!(t.tab_bar(area(), 100, 8).unwrap().height, 1064 / 2);
+        let tiny = Rect {
+            x: 0,
+            y: 0,
+            width: 3,
+            height: 1,
+        };
+        assert!(t.tab_bar(tiny, 2, 8).is_none());
+        assert!(t
+            .rects(tiny, &[1.0; 2], Gaps::uniform(8), 2)
+            .iter()
+            .all(|r| r.width > 0 && r.height > 0));
+        assert!(Tiling::default().tab_bar(area(), 3, 8).is_none());
+    }
+    #[test]
+    fn tabs_divide_the_bar_without_gaps() {
+        let bar = Rect {
+            x: 5,
+            y: 7,
+            width: 100,
+            height: 54,
+        };
+        let row = tabs(bar, 3, false);
+        assert_eq!(row.iter().map(|t| t.width).sum::<u32>(), 100);
+        assert_eq!(row[1].x, row[0].x + row[0].width as i32);
+        let column = tabs(bar, 3, true);
+        assert!(column.iter().all(|t| t.height == 18 && t.width == 100));
+        assert_eq!(column[2].y, 7 + 36);
+    }
+    #[test]
+    fn inner_and_outer_gaps_are_independent() {
+        let gaps = Gaps {
+            inner: 20,
+            outer: 4,
+        };
+        let r = master_stack(area(), &[1.0; 3], 1, gaps, 2, 0.6);
+        assert_eq!((r[0].x, r[0].y), (14, 24));
+        assert_eq!(r[0].x + r[0].width as i32 + 4 + 20, r[1].x);
+        assert_eq!(r[1].y + r[1].height as i32 + 4 + 20, r[2].y);
+        let last = r.last().unwrap();
+        assert_eq!(last.x + last.width as i32 + 4, 10 + 1920 - 4);
+        let d = dwindle(area(), 2, gaps, 2);
+        assert_eq!((d[0].x, d[0].y), (14, 24));
+        assert_eq!(d[0].x + d[0].width as i32 + 4 + 20, d[1].x);
+    }
+    #[test]
+    fn centered_rect_is_clamped_to_area() {
+        assert_eq!(
+            centered(area(), 1000, 500),
+            Rect {
+                x: 470,
+                y: 310,
+                width: 1000,
+                height: 500
+            }
+        );
+        assert_eq!(
+            centered(area(), 5000, 0),
+            Rect {
+                x: 10,
+                y: 559,
+                width: 1920,
+                height: 1
+            }
+        );
+        assert_eq!(area().center(), (970, 560));
+        assert!(area().contains(970, 560));
+    }
+    #[test]
+    fn neighbours_follow_the_master_stack_grid() {
+        let rects = master_stack(area(), &[1.0; 3], 1, Gaps::uniform(8), 2, 0.5);
+        let (master, top, bottom) = (rects[0], rects[1], rects[2]);
+        assert_eq!(neighbour(master, &rects, Direction::Right), Some(1));
+        assert_eq!(neighbour(master, &rects, Direction::Down), None);
+        assert_eq!(neighbour(bottom, &rects, Direction::Left), Some(0));
+        assert_eq!(neighbour(bottom, &rects, Direction::Up), Some(1));
+        assert_eq!(neighbour(top, &rects, Direction::Down), Some(2));
+        assert_eq!(neighbour(top, &rects, Direction::Right), None);
+    }
+    #[test]
+    fn neighbours_prefer_overlap_then_distance() {
+        let at = |x, y| Rect {
+            x,
+            y,
+            width: 100,
+            height: 100,
+        };
+        let from = at(0, 0);
+        let candidates = [at(500, 0), at(150, 300), at(200, 20), at(-2000, 0)];
+        assert_eq!(neighbour(from, &candidates, Direction::Right), Some(2));
+        assert_eq!(neighbour(from, &candidates, Direction::Down), Some(1));
+        assert_eq!(neighbour(from, &candidates, Direction::Left), Some(3));
+        assert_eq!(neighbour(from, &candidates, Direction::Up), None);
+    }
+    #[test]
+    fn maximizing_stretches_only_requested_axes() {
+        let r = Rect {
+            x: 100,
+            y: 200,
+            width: 300,
+            height: 400,
+        };
+        let h = maximized(area(), r, true, false, 2);
+        assert_eq!((h.x, h.width, h.y, h.height), (10, 1916, 200, 400));
+        let v = maximized(area(), r, false, true, 2);
+        assert_eq!((v.x, v.width, v.y, v.height), (100, 300, 20, 1076));
+        assert_eq!(maximized(area(), r, false, false, 2), r);
+        assert_eq!(maximized(area(), r, true, true, 5000).width, 1);
+    }
+    #[test]
+    fn dragging_all_edges_moves_and_single_edges_resize() {
+        let r = Rect {
+            x: 100,
+            y: 100,
+            width: 200,
+            height: 100,
+        };
+        assert_eq!(drag(r, Edges::ALL, -30, 40), Rect { x: 70, y: 140, ..r });
+        let top_left = Edges {
+            left: true,
+            right: false,
+            top: true,
+            bottom: false,
+        };
+        assert_eq!(
+            drag(r, top_left, 50, -20),
+            Rect {
+                x: 150,
+                y: 80,
+                width: 150,
+                height: 120
+            }
+        );
+        let shrunk = drag(r, top_left, 500, 500);
+        assert_eq!((shrunk.x, shrunk.width), (299, 1));
+        assert_eq!((shrunk.y, shrunk.height), (199, 1));
+    }
 }
@@ -1,9 +1,16 @@
 mod commands;
 mod config;
+mod ewmh;
+mod focus;
+mod icccm;
 mod keys;
+mod kill;
 mod layout;
 mod log;
+mod ping;
 mod state;
+mod tabs;
+mod timer;
 mod wm;
 
 fn main() {
@@ -0,0 +1,135 @@
+//! Bookkeeping for `_NET_WM_PING` liveness checks.
+
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+use x11rb::protocol::xproto::Window;
+
+/// How long a client may take to answer before it counts as unresponsive.
+pub const TIMEOUT: Duration = Duration::from_secs(5);
+/// Least time between the pings focus changes send to one window.
+pub const INTERVAL: Duration = Duration::from_secs(30);
+
+struct Ping {
+    token: u32,
+    deadline: Instant,
+    reported: bool,
+}
+
+/// Outstanding pings, at most one per window.
+#[derive(Default)]
+pub struct Pings {
+    serial: u32,
+    pending: HashMap<Window, Ping>,
+    /// When each window was last pinged.
+    sent: HashMap<Window, Instant>,
+}
+
+impl Pings {
+    /// Token for a new ping to `w`, or `None` while one is still unanswered.
+    pub fn send(&mut self, w: Window, now: Instant) -> Option<u32> {
+        if self.pending.contains_key(&w) {
+            return None;
+        }
+        self.serial = self.serial.wrapping_add(1).max(1);
+        self.sent.insert(w, now);
+        self.pending.insert(
+            w,
+            Ping {
+                token: self.serial,
+                deadline: now + TIMEOUT,
+                reported: false,
+            },
+        );
+        Some(self.serial)
+    }
+    /// Record the answer `token` from `w`; whether it matched its ping.
+    pub fn pong(&mut self, w: Window, token: u32) -> bool {
+        if self.pending.get(&w).is_some_and(|p| p.token == token) {
+            self.pending.remove(&w);
+            true
+        } else {
+            false
+        }
+    }
+    /// Windows whose deadline passed by `now`, each reported once; they stay
+    /// pending so a late answer still clears them.
+    pub fn expired(&mut self, now: Instant) -> Vec<Window> {
+        let mut late = Vec::new();
+        for (w, p) in &mut self.pending {
+            if !p.reported && p.deadline <= now {
+                p.reported = true;
+                late.push(*w);
+            }
+        }
+        late
+    }
+    /// Whether a focus change should ping `w`: nothing is outstanding and
+    /// the last ping is at least [`INTERVAL`] old.
+    pub fn due(&self, w: Window, now: Instant) -> bool {
+        !self.pending.contains_key(&w)
+            && self
+                .sent
+                .get(&w)
+                .map_or(true, |t| now.saturating_duration_since(*t) >= INTERVAL)
+    }
+    pub fn forget(&mut self, w: Window) {
+        self.pending.remove(&w);
+        self.sent.remove(&w);
+    }
+    /// Earliest deadline not yet reported.
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.pending
+            .values()
+            .filter(|p| !p.reported)
+            .map(|p| p.deadline)
+            .min()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn one_ping_per_window_until_answered() {
+        let mut p = Pings::default();
+        let now = Instant::now();
+        let token = p.send(1, now).unwrap();
+        assert_eq!(p.send(1, now), None);
+        assert!(!p.pong(1, token + 1));
+        assert!(p.pong(1, token));
+        assert!(p.send(1, now).is_some_and(|t| t != token));
+    }
+    #[test]
+    fn focus_pings_are_rate_limited() {
+        let mut p = Pings::default();
+        let now = Instant::now();
+        assert!(p.due(1, now));
+        let token = p.send(1, now).unwrap();
+        assert!(!p.due(1, now));
+        assert!(p.pong(1, token));
+        assert!(!p.due(1, now + INTERVAL / 2));
+        assert!(p.due(1, now + INTERVAL));
+        p.forget(1);
+        assert!(p.due(1, now));
+    }
+    #[test]
+    fn expiry_is_reported_once_and_late_pongs_still_count() {
+        let mut p = Pings::default();
+        let now = Instant::now();
+        let token = p.send(1, now).unwrap();
+        p.send(2, now + Duration::from_secs(3));
+        assert_eq!(p.next_deadline(), Some(now + TIMEOUT));
+        assert!(p.expired(now + Duration::from_secs(1)).is_empty());
+        assert_eq!(p.expired(now + TIMEOUT), vec![1]);
+        assert!(p.expired(now + TIMEOUT).is_empty());
+        assert_eq!(
+            p.next_deadline(),
+            Some(now + Duration::from_secs(3) + TIMEOUT)
+        );
+        assert!(p.pong(1, token));
+        p.forget(2);
+        assert_eq!(p.next_deadline(), None);
+    }
+}
@@ -1,7 +1,69 @@
-use crate::layout::Rect;
+use crate::layout::{self, Direction, Rect, Tiling, MAX_BORDER, MAX_GAP, MAX_NMASTER};
 use std::collections::HashMap;
 use x11rb::protocol::xproto::Window;
 
+/// Stacking layers, bottom to top.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Layer {
+    Desktop,
+    Below,
+    Tiled,
+    Floating,
+    Above,
+    Dock,
+    Fullscreen,
+    Notification,
+}
+
+/// EWMH `_NET_WM_WINDOW_TYPE` classes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WindowType {
+    #[default]
+    Normal,
+    Dialog,
+    Desktop,
+    Dock,
+    Splash,
+    Utility,
+    Toolbar,
+    Menu,
+    Notification,
+}
+
+impl WindowType {
+    /// Whether clients of this type start floating.
+    pub fn floats(self) -> bool {
+        matches!(
+            self,
+            Self::Dialog | Self::Splash | Self::Utility | Self::Toolbar | Self::Menu
+        )
+    }
+    /// Whether the layout may place clients of this type at all; desktops,
+    /// docks and notifications keep their own geometry and have no border.
+    pub fn arranged(self) -> bool {
+        !matches!(self, Self::Desktop | Self::Dock | Self::Notification)
+    }
+    pub fn takes_focus(self) -> bool {
+        !matches!(self, Self::Desktop | Self::Dock | Self::Notification)
+    }
+}
+
+/// `_NET_WM_STATE` hints tracked per client.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NetState {
+    Fullscreen,
+    Sticky,
+    Above,
+    Below,
+    MaximizedVert,
+    MaximizedHorz,
+    Hidden,
+    DemandsAttention,
+    Modal,
+    SkipTaskbar,
+    SkipPager,
+}
+
 #[derive(Clone, Debug)]
 pub struct Client {
     pub window: Window,
@@ -12,34 +74,211 @@ pub struct Client {
     pub geometry: Rect,
     pub saved_geometry: Option<Rect>,
     pub saved_floating: bool,
+    /// Relative height of the client within its layout column.
+    pub cfact: f32,
+    pub title: String,
+    /// Border width last applied to the X window.
+    pub border_width: u32,
+    /// Index of the configured scratchpad this client belongs to.
+    pub scratchpad: Option<usize>,
+    /// Iconified: kept on its workspace but unmapped and skipped by layout
+    /// and focus.
+    pub minimized: bool,
+    /// Follows the current workspace on every switch.
+    pub sticky: bool,
+    pub above: bool,
+    pub below: bool,
+    pub maximized_vert: bool,
+    pub maximized_horz: bool,
+    /// Geometry to return to once neither axis is maximized.
+    pub saved_maximized: Option<Rect>,
+    pub demands_attention: bool,
+    pub modal: bool,
+    pub skip_taskbar: bool,
+    pub skip_pager: bool,
+    /// Urgency bit of `WM_HINTS`.
+    pub urgent: bool,
+    /// `WM_HINTS.input`: whether the WM may call `SetInputFocus` on it.
+    pub accepts_input: bool,
+    pub window_type: WindowType,
+    /// Missed its last `_NET_WM_PING` deadline.
+    pub unresponsive: bool,
+}
+
+impl Client {
+    pub fn has(&self, state: NetState) -> bool {
+        match state {
+            NetState::Fullscreen => self.fullscreen,
+            NetState::Sticky => self.sticky,
+            NetState::Above => self.above,
+            NetState::Below => self.below,
+            NetState::MaximizedVert => self.maximized_vert,
+            NetState::MaximizedHorz => self.maximized_horz,
+            NetState::Hidden => self.minimized,
+            NetState::DemandsAttention => self.demands_attention,
+            NetState::Modal => self.modal,
+            NetState::SkipTaskbar => self.skip_taskbar,
+            NetState::SkipPager => self.skip_pager,
+        }
+    }
+    /// Record `state` without side effects; above and below exclude each
+    /// other.
+    pub fn set(&mut self, state: NetState, on: bool) {
+        match state {
+            NetState::Fullscreen => self.fullscreen = on,
+            NetState::Sticky => self.sticky = on,
+            NetState::Above => {
+                self.above = on;
+                self.below &= !on;
+            }
+            NetState::Below => {
+                self.below = on;
+                self.above &= !on;
+            }
+            NetState::MaximizedVert => self.maximized_vert = on,
+            NetState::MaximizedHorz => self.maximized_horz = on,
+            NetState::Hidden => self.minimized = on,
+            NetState::DemandsAttention => self.demands_attention = on,
+            NetState::Modal => self.modal = on,
+            NetState::SkipTaskbar => self.skip_taskbar = on,
+            NetState::SkipPager => self.skip_pager = on,
+        }
+    }
+    pub fn maximized(&self) -> bool {
+        self.maximized_vert || self.maximized_horz
+    }
+    /// Sticky, or a desktop, dock, or notification, which stay shown
+    /// without being marked `_NET_WM_STATE_STICKY`.
+    pub fn on_all_workspaces(&self) -> bool {
+        self.sticky || !self.window_type.arranged()
+    }
+    pub fn needs_attention(&self) -> bool {
+        self.urgent || self.demands_attention
+    }
+    pub fn layer(&self) -> Layer {
+        match self.window_type {
+            WindowType::Desktop => return Layer::Desktop,
+            WindowType::Dock => return Layer::Dock,
+            WindowType::Notification => return Layer::Notification,
+            _ => {}
+        }
+        if self.fullscreen {
+            Layer::Fullscreen
+        } else if self.above {
+            Layer::Above
+        } else if self.below {
+            Layer::Below
+        } else if self.floating || self.maximized() {
+            Layer::Floating
+        } else {
+            Layer::Tiled
+        }
+    }
 }
 
 #[derive(Debug)]
 pub struct WmState {
     clients: HashMap<Window, Client>,
+    /// Tiling order per workspace, followed by the hidden scratchpad
+    /// pseudo-workspace.
     order: Vec<Vec<Window>>,
     focus: Vec<Option<Window>>,
+    /// Minimized clients, most recent last.
+    minimized: Vec<Window>,
+    /// Clients needing attention, most recent last.
+    attention: Vec<Window>,
+    /// Clients in the order they were managed.
+    mapping: Vec<Window>,
+    /// Clients from least to most recently raised.
+    raised: Vec<Window>,
+    /// Clients from least to most recently focused.
+    history: Vec<Window>,
+    /// Most-recently-used snapshot and position of a cycle in progress.
+    cycle: Option<(Vec<Window>, usize)>,
+    tiling: Vec<Tiling>,
+    initial_tiling: Vec<Tiling>,
     pub current_workspace: usize,
     pub focused: Option<Window>,
     pub monitors: Vec<Rect>,
-    pub master_ratio: f32,
 }
 
 impl WmState {
-    pub fn new(workspaces: usize, monitors: Vec<Rect>, master_ratio: f32) -> Self {
-        let count = workspaces.max(1);
+    /// Create state with one workspace per entry of `tiling`.
+    pub fn new(monitors: Vec<Rect>, mut tiling: Vec<Tiling>) -> Self {
+        if tiling.is_empty() {
+            tiling.push(Tiling::default());
+        }
+        for t in &mut tiling {
+            t.master_ratio = t.master_ratio.clamp(0.2, 0.8);
+            t.nmaster = t.nmaster.min(MAX_NMASTER);
+            t.gaps.inner = t.gaps.inner.min(MAX_GAP);
+            t.gaps.outer = t.gaps.outer.min(MAX_GAP);
+            t.border_width = t.border_width.min(MAX_BORDER);
+        }
+        let count = tiling.len() + 1;
         Self {
             clients: HashMap::new(),
             order: vec![Vec::new(); count],
             focus: vec![None; count],
+            minimized: Vec::new(),
+            attention: Vec::new(),
+            mapping: Vec::new(),
+            raised: Vec::new(),
+            history: Vec::new(),
+            cycle: None,
+            initial_tiling: tiling.clone(),
+            tiling,
             current_workspace: 0,
             focused: None,
             monitors,
-            master_ratio: master_ratio.clamp(0.2, 0.8),
         }
     }
     pub fn workspace_count(&self) -> usize {
-        self.order.len()
+        self.tiling.len()
+    }
+    fn hidden_workspace(&self) -> usize {
+        self.tiling.len()
+    }
+    /// Layout settings of the current workspace.
+    pub fn tiling(&self) -> Tiling {
+        self.tiling[self.current_workspace]
+    }
+    pub fn cycle_layout(&mut self) {
+        let t = &mut self.tiling[self.current_workspace];
+        t.layout = t.layout.next();
+    }
+    pub fn adjust_master_ratio(&mut self, delta: f32) {
+        let t = &mut self.tiling[self.current_workspace];
+        t.master_ratio = (t.master_ratio + delta).clamp(0.2, 0.8);
+    }
+    pub fn adjust_nmaster(&mut self, delta: isize) {
+        let t = &mut self.tiling[self.current_workspace];
+        t.nmaster = t.nmaster.saturating_add_signed(delta).min(MAX_NMASTER);
+    }
+    /// Widen or narrow both gaps of the current workspace, enabling them.
+    pub fn adjust_gaps(&mut self, delta: i32) {
+        let t = &mut self.tiling[self.current_workspace];
+        let step = |gap: u32| gap.saturating_add_signed(delta).min(MAX_GAP);
+        t.gaps.inner = step(t.gaps.inner);
+        t.gaps.outer = step(t.gaps.outer);
+        t.gaps_enabled = true;
+    }
+    pub fn toggle_gaps(&mut self) {
+        let t = &mut self.tiling[self.current_workspace];
+        t.gaps_enabled = !t.gaps_enabled;
+    }
+    pub fn reset_gaps(&mut self) {
+        let ws = self.current_workspace;
+        self.tiling[ws].gaps = self.initial_tiling[ws].gaps;
+        self.tiling[ws].gaps_enabled = true;
+    }
+    pub fn adjust_border(&mut self, delta: i32) {
+        let t = &mut self.tiling[self.current_workspace];
+        t.border_width = t.border_width.saturating_add_signed(delta).min(MAX_BORDER);
+    }
+    pub fn reset_border(&mut self) {
+        let ws = self.current_workspace;
+        self.tiling[ws].border_width = self.initial_tiling[ws].border_width;
     }
     pub fn client(&self, w: Window) -> Option<&Client> {
         self.clients.get(&w)
@@ -58,7 +297,7 @@ impl WmState {
             return false;
         }
         let w = client.window;
-        let workspace = client.workspace.min(self.order.len() - 1);
+        let workspace = client.workspace.min(self.workspace_count() - 1);
         self.clients.insert(
             w,
             Client {
@@ -66,52 +305,296 @@ impl WmState {
                 ..client
             },
         );
+        let takes_focus = client_takes_focus(&self.clients[&w]);
         self.order[workspace].push(w);
-        if workspace == self.current_workspace {
+        self.mapping.push(w);
+        self.raised.push(w);
+        if workspace == self.current_workspace && takes_focus {
             self.set_focus(Some(w));
         }
         true
     }
     pub fn remove(&mut self, w: Window) -> Option<Client> {
         let client = self.clients.remove(&w)?;
-        let order = &mut self.order[client.workspace];
-        let index = order.iter().position(|id| *id == w).unwrap_or(0);
-        order.retain(|id| *id != w);
-        if self.focus[client.workspace] == Some(w) {
-            self.focus[client.workspace] = order
+        self.minimized.retain(|id| *id != w);
+        self.attention.retain(|id| *id != w);
+        self.mapping.retain(|id| *id != w);
+        self.raised.retain(|id| *id != w);
+        self.history.retain(|id| *id != w);
+        self.detach(w, client.workspace);
+        Some(client)
+    }
+    /// Take `w` out of the order of `workspace` and pick replacement focus.
+    fn detach(&mut self, w: Window, workspace: usize) {
+        self.refocus_without(w, workspace);
+        self.order[workspace].retain(|id| *id != w);
+    }
+    /// Move focus of `workspace` off `w` to its previous, else next, sibling.
+    fn refocus_without(&mut self, w: Window, workspace: usize) {
+        if self.focus[workspace] == Some(w) {
+            let shown = self.focusable(workspace);
+            let index = shown.iter().position(|id| *id == w).unwrap_or(0);
+            let rest = shown.into_iter().filter(|id| *id != w).collect::<Vec<_>>();
+            self.focus[workspace] = rest
                 .get(index.saturating_sub(1))
                 .copied()
-                .or_else(|| order.get(index).copied());
+                .or_else(|| rest.get(index).copied());
         }
         if self.focused == Some(w) {
             self.focused = self.focus[self.current_workspace];
         }
-        Some(client)
+    }
+    /// Clients of `workspace` in tiling order, without minimized ones.
+    fn shown(&self, workspace: usize) -> Vec<Window> {
+        self.order[workspace]
+            .iter()
+            .copied()
+            .filter(|w| !self.clients.get(w).is_some_and(|c| c.minimized))
+            .collect()
+    }
+    /// Shown clients of `workspace` that may receive focus.
+    fn focusable(&self, workspace: usize) -> Vec<Window> {
+        self.shown(workspace)
+            .into_iter()
+            .filter(|w| self.clients.get(w).map_or(true, client_takes_focus))
+            .collect()
+    }
+    fn move_window(&mut self, w: Window, workspace: usize) -> bool {
+        let Some(old) = self.clients.get(&w).map(|c| c.workspace) else {
+            return false;
+        };
+        if old != workspace {
+            self.detach(w, old);
+            self.order[workspace].push(w);
+            if let Some(c) = self.clients.get_mut(&w) {
+                c.workspace = workspace;
+            }
+        }
+        true
+    }
+    /// Client currently bound to scratchpad `index`.
+    pub fn scratchpad(&self, index: usize) -> Option<Window> {
+        self.clients
+            .values()
+            .find(|c| c.scratchpad == Some(index))
+            .map(|c| c.window)
+    }
+    pub fn is_hidden(&self, w: Window) -> bool {
+        self.clients
+            .get(&w)
+            .is_some_and(|c| c.workspace == self.hidden_workspace())
+    }
+    /// Park `w` on the hidden pseudo-workspace.
+    pub fn hide(&mut self, w: Window) -> bool {
+        self.move_window(w, self.hidden_workspace())
+    }
+    /// Bring `w` to the current workspace as a focused floating client.
+    pub fn show(&mut self, w: Window) -> bool {
+        if !self.move_window(w, self.current_workspace) {
+            return false;
+        }
+        if let Some(c) = self.clients.get_mut(&w) {
+            c.floating = true;
+            c.minimized = false;
+        }
+        self.minimized.retain(|id| *id != w);
+        self.set_focus(Some(w));
+        true
+    }
+    pub fn minimize(&mut self, w: Window) -> bool {
+        let Some(workspace) = self
+            .clients
+            .get(&w)
+            .filter(|c| !c.minimized)
+            .map(|c| c.workspace)
+        else {
+            return false;
+        };
+        self.refocus_without(w, workspace);
+        if let Some(c) = self.clients.get_mut(&w) {
+            c.minimized = true;
+        }
+        self.minimized.push(w);
+        true
+    }
+    /// Clear the minimized flag of `w`, focusing it if it is on the current
+    /// workspace.
+    pub fn restore(&mut self, w: Window) -> bool {
+        let Some(c) = self.clients.get_mut(&w).filter(|c| c.minimized) else {
+            return false;
+        };
+        c.minimized = false;
+        let here = c.workspace == self.current_workspace;
+        self.minimized.retain(|id| *id != w);
+        if here {
+            self.set_focus(Some(w));
+        }
+        true
+    }
+    /// Most recently minimized client of the current workspace.
+    pub fn last_minimized(&self) -> Option<Window> {
+        self.minimized.iter().rev().copied().find(|w| {
+            self.clients
+                .get(w)
+                .is_some_and(|c| c.workspace == self.current_workspace)
+        })
+    }
+    /// Refresh the attention order after the urgency of `w` changed. The
+    /// focused client counts as attended and has its urgency cleared; returns
+    /// whether that happened.
+    pub fn update_attention(&mut self, w: Window) -> bool {
+        let focused = self.focused == Some(w);
+        let Some(c) = self.clients.get_mut(&w) else {
+            return false;
+        };
+        let cleared = focused && c.needs_attention();
+        if focused {
+            c.urgent = false;
+            c.demands_attention = false;
+        }
+        let wants = c.needs_attention();
+        self.attention.retain(|id| *id != w);
+        if wants {
+            self.attention.push(w);
+        }
+        cleared
+    }
+    /// Clients in the order they were managed, for `_NET_CLIENT_LIST`.
+    pub fn client_list(&self) -> &[Window] {
+        &self.mapping
+    }
+    /// Put `w` on top of the other clients of its layer.
+    pub fn raise(&mut self, w: Window) {
+        if let Some(index) = self.raised.iter().position(|id| *id == w) {
+            let w = self.raised.remove(index);
+            self.raised.push(w);
+        }
+    }
+    /// Every client from bottom to top: by layer, then by when it was raised.
+    pub fn stacking(&self) -> Vec<Window> {
+        let mut stack = self.raised.clone();
+        stack.sort_by_key(|w| self.clients.get(w).map(Client::layer));
+        stack
+    }
+    /// Client that most recently asked for attention.
+    pub fn last_attention(&self) -> Option<Window> {
+        self.attention.last().copied()
+    }
+    /// Whether `w` should currently be mapped.
+    pub fn is_shown(&self, w: Window) -> bool {
+        self.clients
+            .get(&w)
+            .is_some_and(|c| c.workspace == self.current_workspace && !c.minimized)
     }
     pub fn visible(&self) -> Vec<Window> {
-        self.order[self.current_workspace].clone()
+        self.shown(self.current_workspace)
     }
     pub fn tiled_on(&self, monitor: usize) -> Vec<Window> {
-        self.order[self.current_workspace]
-            .iter()
-            .copied()
+        self.shown(self.current_workspace)
+            .into_iter()
             .filter(|w| {
-                self.clients
-                    .get(w)
-                    .is_some_and(|c| c.monitor == monitor && !c.floating && !c.fullscreen)
+                self.clients.get(w).is_some_and(|c| {
+                    c.monitor == monitor
+                        && c.window_type.arranged()
+                        && !c.floating
+                        && !c.fullscreen
+                        && !c.maximized()
+                })
             })
             .collect()
     }
+    /// Grow or shrink the focused client relative to its column siblings;
+    /// `None` resets it to an equal share.
+    pub fn adjust_cfact(&mut self, delta: Option<f32>) {
+        let Some(c) = self.focused.and_then(|w| self.clients.get_mut(&w)) else {
+            return;
+        };
+        c.cfact = delta.map_or(1.0, |d| (c.cfact + d).clamp(0.25, 4.0));
+    }
+    /// Layout weights of the clients in `ids`.
+    pub fn weights(&self, ids: &[Window]) -> Vec<f32> {
+        ids.iter()
+            .map(|w| self.clients.get(w).map_or(1.0, |c| c.cfact))
+            .collect()
+    }
     pub fn set_focus(&mut self, w: Option<Window>) {
         self.focused = w.filter(|id| {
             self.clients
                 .get(id)
-                .is_some_and(|c| c.workspace == self.current_workspace)
+                .is_some_and(|c| c.workspace == self.current_workspace && client_takes_focus(c))
         });
         self.focus[self.current_workspace] = self.focused;
     }
+    /// Record the focused client as the most recently used one, unless a
+    /// cycle is in progress.
+    pub fn remember_focus(&mut self) {
+        if self.cycle.is_some() {
+            return;
+        }
+        if let Some(w) = self.focused {
+            self.history.retain(|id| *id != w);
+            self.history.push(w);
+        }
+    }
+    /// Focusable clients of `workspace`, most recently focused first; ones
+    /// never focused follow, newest first.
+    pub fn recent(&self, workspace: usize) -> Vec<Window> {
+        let focusable = self.focusable(workspace);
+        let mut recent = self
+            .history
+            .iter()
+            .rev()
+            .copied()
+            .filter(|w| focusable.contains(w))
+            .collect::<Vec<_>>();
+        for w in focusable.into_iter().rev() {
+            if !recent.contains(&w) {
+                recent.push(w);
+            }
+        }
+        recent
+    }
+    /// Most recently focused client other than the focused one, on any
+    /// workspace, that is neither minimized nor a hidden scratchpad.
+    pub fn previous_window(&self) -> Option<Window> {
+        self.history.iter().rev().copied().find(|w| {
+            Some(*w) != self.focused
+                && self.clients.get(w).is_some_and(|c| {
+                    !c.minimized && c.workspace != self.hidden_workspace() && client_takes_focus(c)
+                })
+        })
+    }
+    /// Step through the recent clients of the current workspace, starting a
+    /// cycle if none is in progress. History stays untouched until
+    /// [`Self::end_cycle`]; returns false when there is nothing to cycle to.
+    pub fn cycle_recent(&mut self, delta: isize) -> bool {
+        let started = self.cycle.is_some();
+        let (mut recent, index) = self
+            .cycle
+            .take()
+            .unwrap_or_else(|| (self.recent(self.current_workspace), 0));
+        let focusable = self.focusable(self.current_workspace);
+        recent.retain(|w| focusable.contains(w));
+        if recent.len() < 2 {
+            self.cycle = started.then_some((recent, 0));
+            return false;
+        }
+        let index = (index.min(recent.len() - 1) as isize + delta).rem_euclid(recent.len() as isize)
+            as usize;
+        self.set_focus(Some(recent[index]));
+        self.cycle = Some((recent, index));
+        true
+    }
+    pub fn cycling(&self) -> bool {
+        self.cycle.is_some()
+    }
+    /// Finish a cycle, making the client it landed on the most recent.
+    pub fn end_cycle(&mut self) {
+        self.cycle = None;
+        self.remember_focus();
+    }
     pub fn focus_cycle(&mut self, delta: isize) {
-        let order = &self.order[self.current_workspace];
+        let order = self.focusable(self.current_workspace);
         if order.is_empty() {
             self.set_focus(None);
             return;
@@ -124,18 +607,33 @@ impl WmState {
         self.set_focus(Some(order[next]));
     }
     pub fn switch_workspace(&mut self, workspace: usize) -> bool {
-        if workspace >= self.order.len() || workspace == self.current_workspace {
+        if workspace >= self.workspace_count() || workspace == self.current_workspace {
             return false;
         }
+        // Sticky clients keep their index so the tiling does not shuffle.
+        let sticky = self.order[self.current_workspace]
+            .iter()
+            .copied()
+            .enumerate()
+            .filter(|(_, w)| self.clients.get(w).is_some_and(|c| c.on_all_workspaces()))
+            .collect::<Vec<_>>();
+        for (index, w) in sticky {
+            self.detach(w, self.current_workspace);
+            let order = &mut self.order[workspace];
+            order.insert(index.min(order.len()), w);
+            if let Some(c) = self.clients.get_mut(&w) {
+                c.workspace = workspace;
+            }
+        }
         self.current_workspace = workspace;
         self.focused = self.focus[workspace]
             .filter(|w| self.clients.contains_key(w))
-            .or_else(|| self.order[workspace].last().copied());
+            .or_else(|| self.focusable(workspace).last().copied());
         self.focus[workspace] = self.focused;
         true
     }
     pub fn move_focused_to_workspace(&mut self, workspace: usize) -> Option<Window> {
-        if workspace >= self.order.len() {
+        if workspace >= self.workspace_count() {
             return None;
         }
         let w = self.focused?;
@@ -144,18 +642,74 @@ impl WmState {
         self.order[workspace].push(w);
         self.clients.get_mut(&w)?.workspace = workspace;
         self.focus[workspace] = Some(w);
-        self.focused = self.order[old].last().copied();
+        self.focused = self.focusable(old).last().copied();
         self.focus[old] = self.focused;
         Some(w)
     }
     pub fn reorder(&mut self, delta: isize) {
-        let order = &mut self.order[self.current_workspace];
+        let shown = self.shown(self.current_workspace);
         let Some(w) = self.focused else { return };
-        let Some(index) = order.iter().position(|id| *id == w) else {
+        let Some(index) = shown.iter().position(|id| *id == w) else {
             return;
         };
-        let other = (index as isize + delta).rem_euclid(order.len() as isize) as usize;
-        order.swap(index, other);
+        let other = shown[(index as isize + delta).rem_euclid(shown.len() as isize) as usize];
+        let order = &mut self.order[self.current_workspace];
+        if let (Some(a), Some(b)) = (
+            order.iter().position(|id| *id == w),
+            order.iter().position(|id| *id == other),
+        ) {
+            order.swap(a, b);
+        }
+    }
+    /// Nearest client of `candidates` to the focused one in `direction`,
+    /// by geometry and across monitors.
+    fn neighbour(&self, candidates: &[Window], direction: Direction) -> Option<Window> {
+        let from = self.clients.get(&self.focused?)?.geometry;
+        let others = candidates
+            .iter()
+            .copied()
+            .filter(|w| Some(*w) != self.focused)
+            .collect::<Vec<_>>();
+        let rects = others
+            .iter()
+            .filter_map(|w| self.clients.get(w).map(|c| c.geometry))
+            .collect::<Vec<_>>();
+        layout::neighbour(from, &rects, direction).map(|i| others[i])
+    }
+    pub fn focus_direction(&mut self, direction: Direction) -> bool {
+        let target = self.neighbour(&self.focusable(self.current_workspace), direction);
+        if target.is_some() {
+            self.set_focus(target);
+        }
+        target.is_some()
+    }
+    /// Swap the focused tiled client with its tiled neighbour in
+    /// `direction`, taking over its place in the order and its monitor.
+    pub fn swap_direction(&mut self, direction: Direction) -> bool {
+        let tiled = (0..self.monitors.len())
+            .flat_map(|m| self.tiled_on(m))
+            .collect::<Vec<_>>();
+        let Some(w) = self.focused.filter(|w| tiled.contains(w)) else {
+            return false;
+        };
+        let Some(other) = self.neighbour(&tiled, direction) else {
+            return false;
+        };
+        let order = &mut self.order[self.current_workspace];
+        if let (Some(a), Some(b)) = (
+            order.iter().position(|id| *id == w),
+            order.iter().position(|id| *id == other),
+        ) {
+            order.swap(a, b);
+        }
+        let monitors = (self.clients[&w].monitor, self.clients[&other].monitor);
+        if let Some(c) = self.clients.get_mut(&w) {
+            c.monitor = monitors.1;
+        }
+        if let Some(c) = self.clients.get_mut(&other) {
+            c.monitor = monitors.0;
+        }
+        true
     }
     pub fn promote(&mut self) {
         if let Some(w) = self.focused {
@@ -167,9 +721,14 @@ impl WmState {
     }
 }
 
+fn client_takes_focus(c: &Client) -> bool {
+    !c.minimized && c.window_type.takes_focus()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::layout::{Gaps, Layout};
     fn client(w: Window, ws: usize) -> Client {
         Client {
             window: w,
@@ -180,18 +739,36 @@ mod tests {
             geometry: Rect::default(),
             saved_geometry: None,
             saved_floating: false,
+            cfact: 1.0,
+            title: String::new(),
+            border_width: 0,
+            scratchpad: None,
+            minimized: false,
+            sticky: false,
+            above: false,
+            below: false,
+            maximized_vert: false,
+            maximized_horz: false,
+            saved_maximized: None,
+            demands_attention: false,
+            modal: false,
+            skip_taskbar: false,
+            skip_pager: false,
+            urgent: false,
+            accepts_input: true,
+            window_type: WindowType::Normal,
+            unresponsive: false,
         }
     }
     fn state() -> WmState {
         WmState::new(
-            3,
             vec![Rect {
                 x: 0,
                 y: 0,
                 width: 800,
                 height: 600,
             }],
-            0.6,
+            vec![Tiling::default(); 3],
         )
     }
     #[test]
@@ -272,4 +849,353 @@ mod tests {
         c.floating = c.saved_floating;
         assert_eq!(c.saved_geometry.take().unwrap().x, 1);
     }
+    #[test]
+    fn nmaster_is_per_workspace_and_bounded() {
+        let mut s = state();
+        s.adjust_nmaster(1);
+        assert_eq!(s.tiling().nmaster, 2);
+        s.switch_workspace(1);
+        assert_eq!(s.tiling().nmaster, 1);
+        s.adjust_nmaster(-5);
+        assert_eq!(s.tiling().nmaster, 0);
+        s.adjust_nmaster(100);
+        assert_eq!(s.tiling().nmaster, MAX_NMASTER);
+        s.switch_workspace(0);
+        assert_eq!(s.tiling().nmaster, 2);
+    }
+    #[test]
+    fn layout_and_ratio_are_per_workspace() {
+        let mut s = state();
+        s.adjust_master_ratio(0.1);
+        s.cycle_layout();
+        s.switch_workspace(2);
+        assert_eq!(s.tiling(), Tiling::default());
+        s.adjust_master_ratio(-1.0);
+        assert_eq!(s.tiling().master_ratio, 0.2);
+        s.switch_workspace(0);
+        assert!((s.tiling().master_ratio - 0.7).abs() < f32::EPSILON);
+        assert_eq!(s.tiling().layout, Layout::Spiral);
+    }
+    #[test]
+    fn initial_tiling_is_clamped() {
+        let s = WmState::new(
+            Vec::new(),
+            vec![Tiling {
+                master_ratio: 3.0,
+                nmaster: 50,
+                ..Tiling::default()
+            }],
+        );
+        assert_eq!(s.tiling().master_ratio, 0.8);
+        assert_eq!(s.tiling().nmaster, MAX_NMASTER);
+    }
+    #[test]
+    fn cfact_applies_to_focused_client_within_bounds() {
+        let mut s = state();
+        s.add(client(1, 0));
+        s.add(client(2, 0));
+        for _ in 0..20 {
+            s.adjust_cfact(Some(0.25));
+        }
+        assert_eq!(s.weights(&[1, 2, 3]), vec![1.0, 4.0, 1.0]);
+        s.adjust_cfact(None);
+        assert_eq!(s.client(2).unwrap().cfact, 1.0);
+        s.adjust_cfact(Some(-10.0));
+        assert_eq!(s.client(2).unwrap().cfact, 0.25);
+    }
+    #[test]
+    fn gaps_adjust_toggle_and_reset_per_workspace() {
+        let mut s = state();
+        s.adjust_gaps(4);
+        assert_eq!(s.tiling().gaps, Gaps::uniform(12));
+        s.toggle_gaps();
+        assert!(!s.tiling().gaps_enabled);
+        s.adjust_gaps(-20);
+        assert_eq!(s.tiling().gaps, Gaps::uniform(0));
+        assert!(s.tiling().gaps_enabled);
+        s.adjust_gaps(500);
+        assert_eq!(s.tiling().gaps, Gaps::uniform(MAX_GAP));
+        s.switch_workspace(1);
+        assert_eq!(s.tiling().gaps, Gaps::uniform(8));
+        s.switch_workspace(0);
+        s.toggle_gaps();
+        s.reset_gaps();
+        assert_eq!(s.tiling().gaps, Gaps::uniform(8));
+        assert!(s.tiling().gaps_enabled);
+    }
+    #[test]
+    fn border_width_is_bounded_and_resettable() {
+        let mut s = state();
+        s.adjust_border(-5);
+        assert_eq!(s.tiling().border_width, 0);
+        s.adjust_border(50);
+        assert_eq!(s.tiling().border_width, MAX_BORDER);
+        s.reset_border();
+        assert_eq!(s.tiling().border_width, 2);
+    }
+    #[test]
+    fn scratchpad_hides_outside_every_workspace() {
+        let mut s = state();
+        s.add(client(1, 0));
+        s.add(Client {
+            scratchpad: Some(0),
+            ..client(2, 0)
+        });
+        assert_eq!(s.scratchpad(0), Some(2));
+        assert!(s.hide(2));
+        assert!(s.is_hidden(2));
+        assert_eq!(s.visible(), vec![1]);
+        assert_eq!(s.focused, Some(1));
+        assert!(!s.switch_workspace(3));
+        assert_eq!(s.move_focused_to_workspace(3), None);
+        s.switch_workspace(1);
+        assert!(s.show(2));
+        assert_eq!(s.visible(), vec![2]);
+        assert_eq!(s.focused, Some(2));
+        assert!(s.client(2).unwrap().floating);
+        s.remove(2);
+        assert_eq!(s.scratchpad(0), None);
+    }
+    #[test]
+    fn minimized_clients_leave_layout_and_focus() {
+        let mut s = state();
+        for w in 1..=3 {
+            s.add(client(w, 0));
+        }
+        assert!(s.minimize(3));
+        assert!(!s.minimize(3));
+        assert!(s.minimize(1));
+        assert_eq!(s.visible(), vec![2]);
+        assert_eq!(s.tiled_on(0), vec![2]);
+        assert_eq!(s.focused, Some(2));
+        s.focus_cycle(1);
+        assert_eq!(s.focused, Some(2));
+        s.set_focus(Some(3));
+        assert_eq!(s.focused, None);
+        assert_eq!(s.last_minimized(), Some(1));
+        assert!(s.restore(1));
+        assert_eq!(s.focused, Some(1));
+        assert_eq!(s.visible(), vec![1, 2]);
+        assert_eq!(s.last_minimized(), Some(3));
+        s.remove(3);
+        assert_eq!(s.last_minimized(), None);
+    }
+    #[test]
+    fn shown_clients_are_on_current_workspace_and_not_minimized() {
+        let mut s = state();
+        s.add(client(1, 0));
+        s.add(client(2, 1));
+        s.add(client(3, 0));
+        s.minimize(3);
+        assert!(s.is_shown(1));
+        assert!(!s.is_shown(2));
+        assert!(!s.is_shown(3));
+        assert!(!s.is_shown(4));
+        s.switch_workspace(1);
+        assert!(s.is_shown(2) && !s.is_shown(1));
+    }
+    #[test]
+    fn sticky_clients_follow_workspace_switches() {
+        let mut s = state();
+        s.add(client(1, 0));
+        s.add(Client {
+            sticky: true,
+            ..client(2, 0)
+        });
+        s.add(client(3, 1));
+        s.switch_workspace(1);
+        assert_eq!(s.visible(), vec![3, 2]);
+        assert_eq!(s.focused, Some(2));
+        s.switch_workspace(0);
+        assert_eq!(s.visible(), vec![1, 2]);
+        assert_eq!(s.focused, Some(1));
+        s.switch_workspace(2);
+        assert_eq!(s.visible(), vec![2]);
+        assert_eq!(s.focused, Some(2));
+    }
+    #[test]
+    fn sticky_clients_keep_their_position() {
+        let mut s = state();
+        s.add(client(1, 0));
+        s.add(Client {
+            sticky: true,
+            ..client(2, 0)
+        });
+        s.add(client(3, 0));
+        s.add(client(4, 1));
+        s.add(client(5, 1));
+        s.switch_workspace(1);
+        assert_eq!(s.visible(), vec![4, 2, 5]);
+        s.switch_workspace(0);
+        assert_eq!(s.visible(), vec![1, 2, 3]);
+        s.switch_workspace(1);
+        assert_eq!(s.visible(), vec![4, 2, 5]);
+    }
+    #[test]
+    fn net_states_round_trip_and_above_excludes_below() {
+        let mut c = client(1, 0);
+        c.set(NetState::Below, true);
+        c.set(NetState::Above, true);
+        assert!(c.has(NetState::Above) && !c.has(NetState::Below));
+        c.set(NetState::Below, true);
+        assert!(!c.has(NetState::Above) && c.has(NetState::Below));
+        c.set(NetState::SkipPager, true);
+        assert!(c.has(NetState::SkipPager) && !c.has(NetState::SkipTaskbar));
+        c.set(NetState::Hidden, true);
+        assert!(c.minimized);
+    }
+    #[test]
+    fn maximized_clients_leave_the_tiling() {
+        let mut s = state();
+        s.add(client(1, 0));
+        s.add(Client {
+            maximized_vert: true,
+            ..client(2, 0)
+        });
+        assert_eq!(s.tiled_on(0), vec![1]);
+        assert_eq!(s.visible(), vec![1, 2]);
+    }
+    #[test]
+    fn attention_is_ordered_and_cleared_by_focus() {
+        let mut s = state();
+        s.add(client(1, 0));
+        s.add(client(2, 1));
+        s.add(client(3, 0));
+        s.client_mut(2).unwrap().urgent = true;
+        s.update_attention(2);
+        s.client_mut(1).unwrap().demands_attention = true;
+        s.update_attention(1);
+        assert_eq!(s.last_attention(), Some(1));
+        s.set_focus(Some(1));
+        assert!(s.update_attention(1));
+        assert!(!s.client(1).unwrap().needs_attention());
+        assert_eq!(s.last_attention(), Some(2));
+        s.set_focus(Some(3));
+        s.client_mut(3).unwrap().urgent = true;
+        assert!(s.update_attention(3));
+        assert_eq!(s.last_attention(), Some(2));
+        s.remove(2);
+        assert_eq!(s.last_attention(), None);
+    }
+    #[test]
+    fn stacking_orders_layers_then_raises() {
+        let mut s = state();
+        for w in 1..=5 {
+            s.add(client(w, 0));
+        }
+        s.client_mut(1).unwrap().floating = true;
+        s.client_mut(2).unwrap().fullscreen = true;
+        s.client_mut(3).unwrap().below = true;
+        s.client_mut(5).unwrap().above = true;
+        assert_eq!(s.stacking(), vec![3, 4, 1, 5, 2]);
+        s.add(Client {
+            floating: true,
+            ..client(6, 1)
+        });
+        s.raise(1);
+        assert_eq!(s.stacking(), vec![3, 4, 6, 1, 5, 2]);
+        s.remove(4);
+        assert_eq!(s.client_list(), &[1, 2, 3, 5, 6]);
+    }
+    #[test]
+    fn window_types_control_tiling_focus_and_layer() {
+        let mut s = state();
+        s.add(client(1, 0));
+        s.add(Client {
+            window_type: WindowType::Dock,
+            ..client(2, 0)
+        });
+        s.add(Client {
+            window_type: WindowType::Desktop,
+            ..client(3, 0)
+        });
+        s.add(Client {
+            window_type: WindowType::Notification,
+            ..client(4, 0)
+        });
+        assert_eq!(s.focused, Some(1));
+        assert_eq!(s.tiled_on(0), vec![1]);
+        assert_eq!(s.visible(), vec![1, 2, 3, 4]);
+        s.set_focus(Some(3));
+        assert_eq!(s.focused, None);
+        s.focus_cycle(1);
+        assert_eq!(s.focused, Some(1));
+        s.focus_cycle(1);
+        assert_eq!(s.focused, Some(1));
+        assert_eq!(s.stacking(), vec![3, 1, 2, 4]);
+        assert!(!s.client(2).unwrap().has(NetState::Sticky));
+        s.switch_workspace(1);
+        assert_eq!(s.visible(), vec![2, 3, 4]);
+        assert_eq!(s.focused, None);
+        assert!(WindowType::Splash.floats() && !WindowType::Dock.floats());
+    }
+    #[test]
+    fn recent_cycle_commits_history_only_when_ended() {
+        let mut s = state();
+        for w in 1..=3 {
+            s.add(client(w, 0));
+        }
+        s.add(client(4, 1));
+        s.set_focus(Some(1));
+        s.remember_focus();
+        s.set_focus(Some(3));
+        s.remember_focus();
+        assert_eq!(s.recent(0), vec![3, 1, 2]);
+        assert!(s.cycle_recent(1));
+        assert_eq!(s.focused, Some(1));
+        assert!(s.cycle_recent(1));
+        s.remember_focus();
+        assert_eq!(s.focused, Some(2));
+        assert_eq!(s.recent(0), vec![3, 1, 2]);
+        s.end_cycle();
+        assert!(!s.cycling());
+        assert_eq!(s.recent(0), vec![2, 3, 1]);
+        assert_eq!(s.previous_window(), Some(3));
+        assert!(s.cycle_recent(-1));
+        assert_eq!(s.focused, Some(1));
+        s.end_cycle();
+        s.remove(3);
+        s.remove(2);
+        assert!(!s.cycle_recent(1));
+        assert!(!s.cycling());
+        s.switch_workspace(1);
+        s.remember_focus();
+        assert_eq!(s.previous_window(), Some(1));
+    }
+    #[test]
+    fn directional_focus_and_swap_use_geometry() {
+        let mut s = state();
+        let at = |x, y, height| Rect {
+            x,
+            y,
+            width: 100,
+            height,
+        };
+        for (w, r) in [
+            (1, at(0, 0, 200)),
+            (2, at(100, 0, 100)),
+            (3, at(100, 100, 100)),
+        ] {
+            s.add(Client {
+                geometry: r,
+                ..client(w, 0)
+            });
+        }
+        s.add(Client {
+            geometry: at(300, 0, 100),
+            floating: true,
+            ..client(4, 0)
+        });
+        s.set_focus(Some(3));
+        assert!(s.focus_direction(Direction::Up));
+        assert_eq!(s.focused, Some(2));
+        assert!(!s.focus_direction(Direction::Up));
+        assert!(s.focus_direction(Direction::Right));
+        assert_eq!(s.focused, Some(4));
+        assert!(!s.swap_direction(Direction::Left));
+        s.set_focus(Some(1));
+        assert!(s.swap_direction(Direction::Right));
+        assert_eq!(s.focused, Some(1));
+        assert_eq!(s.tiled_on(0), vec![2, 1, 3]);
+    }
 }
@@ -0,0 +1,265 @@
+//! Title strips drawn by BoringWM itself for tabbed and stacked layouts.
+
+use crate::layout::{self, Rect};
+use anyhow::{Context, Result};
+use log::warn;
+use x11rb::{connection::Connection, protocol::xproto::*, rust_connection::RustConnection};
+
+struct Bar {
+    window: Window,
+    rect: Rect,
+    stacked: bool,
+    clients: Vec<Window>,
+    mapped: bool,
+}
+
+/// The core font titles are drawn with.
+struct TitleFont {
+    font: Font,
+    ascent: i16,
+    descent: i16,
+    char_width: u32,
+}
+
+impl TitleFont {
+    fn open(conn: &RustConnection) -> Result<Self> {
+        let font = conn.generate_id()?;
+        conn.open_font(font, b"fixed")?
+            .check()
+            .context("cannot open the X core font \"fixed\"")?;
+        let info = conn.query_font(font)?.reply()?;
+        Ok(Self {
+            font,
+            ascent: info.font_ascent,
+            descent: info.font_descent,
+            char_width: u32::from(info.max_bounds.character_width.max(1) as u16),
+        })
+    }
+}
+
+/// One override-redirect title window per monitor, created on first use.
+pub struct TabBars {
+    root: Window,
+    /// `None` when the font is missing; strips are then drawn untitled.
+    font: Option<TitleFont>,
+    gc: Gcontext,
+    bars: Vec<Option<Bar>>,
+}
+
+impl TabBars {
+    pub fn new(conn: &RustConnection, root: Window) -> Result<Self> {
+        let font = TitleFont::open(conn)
+            .map_err(|e| warn!("tab titles disabled: {e:#}"))
+            .ok();
+        let gc = conn.generate_id()?;
+        let mut aux = CreateGCAux::new().graphics_exposures(0);
+        if let Some(f) = &font {
+            aux = aux.font(f.font);
+        }
+        conn.create_gc(gc, root, &aux)?;
+        Ok(Self {
+            root,
+            font,
+            gc,
+            bars: Vec::new(),
+        })
+    }
+    /// Show the bar of `monitor` at `rect` listing `clients`, or hide it.
+    pub fn place(
+        &mut self,
+        conn: &RustConnection,
+        monitor: usize,
+        rect: Option<Rect>,
+        stacked: bool,
+        clients: Vec<Window>,
+    ) {
+        if self.bars.len() <= monitor {
+            self.bars.resize_with(monitor + 1, || None);
+        }
+        let Some(rect) = rect else {
+            if let Some(bar) = self.bars[monitor].as_mut().filter(|b| b.mapped) {
+                let _ = conn.unmap_window(bar.window);
+                bar.mapped = false;
+            }
+            return;
+        };
+        if self.bars[monitor].is_none() {
+            let Ok(window) = conn.generate_id() else {
+                return;
+            };
+            let _ = conn.create_window(
+                x11rb::COPY_FROM_PARENT as u8,
+                window,
+                self.root,
+                rect.x as i16,
+                rect.y as i16,
+                rect.width as u16,
+                rect.height as u16,
+                0,
+                WindowClass::INPUT_OUTPUT,
+                x11rb::COPY_FROM_PARENT,
+                &CreateWindowAux::new()
+                    .override_redirect(1)
+                    .event_mask(EventMask::EXPOSURE | EventMask::BUTTON_PRESS),
+            );
+            self.bars[monitor] = Some(Bar {
+                window,
+                rect,
+                stacked,
+                clients: Vec::new(),
+                mapped: false,
+            });
+        }
+        let Some(bar) = self.bars[monitor].as_mut() else {
+            return;
+        };
+        let _ = conn.configure_window(
+            bar.window,
+            &ConfigureWindowAux::new()
+                .x(rect.x)
+                .y(rect.y)
+                .width(rect.width)
+                .height(rect.height),
+        );
+        if !bar.mapped {
+            let _ = conn.map_window(bar.window);
+            bar.mapped = true;
+        }
+        bar.rect = rect;
+        bar.stacked = stacked;
+        bar.clients = clients;
+    }
+    /// Repaint every visible bar; `title` returns the label and whether the
+    /// client is focused.
+    pub fn draw(
+        &self,
+        conn: &RustConnection,
+        colors: (u32, u32),
+        title: impl Fn(Window) -> (String, bool),
+    ) {
+        for bar in self.bars.iter().flatten().filter(|b| b.mapped) {
+            let local = Rect {
+                x: 0,
+                y: 0,
+                ..bar.rect
+            };
+            let _ = conn.change_gc(self.gc, &ChangeGCAux::new().foreground(0));
+            let _ = conn.poly_fill_rectangle(bar.window, self.gc, &[rectangle(local)]);
+            let cells = layout::tabs(local, bar.clients.len(), bar.stacked);
+            for (w, cell) in bar.clients.iter().zip(cells) {
+                let (text, focused) = title(*w);
+                let background = if focused { colors.0 } else { colors.1 };
+                let fill = Rect {
+                    width: cell.width.saturating_sub(u32::from(!bar.stacked)).max(1),
+                    height: cell.height.saturating_sub(u32::from(bar.stacked)).max(1),
+                    ..cell
+                };
+                let _ = conn.change_gc(
+                    self.gc,
+                    &ChangeGCAux::new()
+                        .foreground(background)
+                        .background(background),
+                );
+                let _ = conn.poly_fill_rectangle(bar.window, self.gc, &[rectangle(fill)]);
+                let Some(font) = &self.font else {
+                    continue;
+                };
+                let _ = conn.change_gc(
+                    self.gc,
+                    &ChangeGCAux::new().foreground(text_color(background)),
+                );
+                let columns = fill.width.saturating_sub(8) / font.char_width;
+                let baseline =
+                    fill.y as i16 + (fill.height as i16 + font.ascent - font.descent) / 2;
+                let _ = conn.image_text8(
+                    bar.window,
+                    self.gc,
+                    fill.x as i16 + 4,
+                    baseline,
+                    &label(&text, columns as usize),
+                );
+            }
+        }
+    }
+    /// Keep every visible bar directly above `sibling`.
+    pub fn stack_above(&self, conn: &RustConnection, sibling: Window) {
+        for bar in self.bars.iter().flatten().filter(|b| b.mapped) {
+            let _ = conn.configure_window(
+                bar.window,
+                &ConfigureWindowAux::new()
+                    .sibling(sibling)
+                    .stack_mode(StackMode::ABOVE),
+            );
+        }
+    }
+    pub fn owns(&self, window: Window) -> bool {
+        self.bars.iter().flatten().any(|b| b.window == window)
+    }
+    /// Client whose title is at bar-relative coordinates `x`, `y`.
+    pub fn hit(&self, window: Window, x: i16, y: i16) -> Option<Window> {
+        let bar = self.bars.iter().flatten().find(|b| b.window == window)?;
+        let local = Rect {
+            x: 0,
+            y: 0,
+            ..bar.rect
+        };
+        layout::tabs(local, bar.clients.len(), bar.stacked)
+            .iter()
+            .position(|cell| cell.contains(x.into(), y.into()))
+            .map(|index| bar.clients[index])
+    }
+    pub fn destroy(&self, conn: &RustConnection) {
+        for bar in self.bars.iter().flatten() {
+            let _ = conn.destroy_window(bar.window);
+        }
+        let _ = conn.free_gc(self.gc);
+        if let Some(f) = &self.font {
+            let _ = conn.close_font(f.font);
+        }
+    }
+}
+
+fn rectangle(r: Rect) -> Rectangle {
+    Rectangle {
+        x: r.x as i16,
+        y: r.y as i16,
+        width: r.width as u16,
+        height: r.height as u16,
+    }
+}
+
+/// Black or white, whichever reads better on `background`.
+fn text_color(background: u32) -> u32 {
+    let channel = |shift: u32| (background >> shift) & 0xff;
+    let luma = 299 * channel(16) + 587 * channel(8) + 114 * channel(0);
+    if luma > 140_000 {
+        0x000000
+    } else {
+        0xffffff
+    }
+}
+
+/// Latin-1 bytes for the core "fixed" font, cut to `columns` characters.
+fn label(title: &str, columns: usize) -> Vec<u8> {
+    title
+        .chars()
+        .take(columns.min(255))
+        .map(|c| u8::try_from(u32::from(c)).unwrap_or(b'?'))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn text_contrasts_with_background() {
+        assert_eq!(text_color(0x88ccff), 0x000000);
+        assert_eq!(text_color(0x333333), 0xffffff);
+    }
+    #[test]
+    fn labels_are_latin1_and_truncated() {
+        assert_eq!(label("vim — main.rs", 7), b"vim ? m");
+        assert_eq!(label("caf\u{e9}", 10), b"caf\xe9");
+        assert!(label("", 5).is_empty());
+    }
+}
@@ -0,0 +1,89 @@
+//! Waking the event loop when a ping or kill deadline passes.
+
+use anyhow::{Context, Result};
+use std::{
+    collections::BTreeSet,
+    sync::mpsc::{self, Receiver, RecvTimeoutError, Sender},
+    thread,
+    time::Instant,
+};
+use x11rb::{connection::Connection, protocol::xproto::*, rust_connection::RustConnection};
+
+/// A thread that sends an empty `ClientMessage` to a window of the event
+/// loop at each deadline, so the loop can block in `wait_for_event`.
+pub struct Timer {
+    deadlines: Sender<Instant>,
+    armed: Option<Instant>,
+}
+
+impl Timer {
+    /// Start the thread on its own connection; messages go to `window`,
+    /// which must belong to the event loop's connection.
+    pub fn spawn(window: Window) -> Result<Self> {
+        let (conn, _) = x11rb::connect(None).context("cannot connect timer to X11 display")?;
+        let (deadlines, receiver) = mpsc::channel();
+        thread::spawn(move || run(&conn, window, receiver));
+        Ok(Self {
+            deadlines,
+            armed: None,
+        })
+    }
+    /// Wake the event loop at `deadline`, unless that was already asked for.
+    pub fn wake_at(&mut self, deadline: Option<Instant>) {
+        if deadline == self.armed {
+            return;
+        }
+        if let Some(d) = deadline {
+            let _ = self.deadlines.send(d);
+        }
+        self.armed = deadline;
+    }
+}
+
+fn run(conn: &RustConnection, window: Window, deadlines: Receiver<Instant>) {
+    let mut pending = BTreeSet::<Instant>::new();
+    loop {
+        let received = match pending.first() {
+            Some(d) => deadlines.recv_timeout(d.saturating_duration_since(Instant::now())),
+            None => deadlines.recv().map_err(|_| RecvTimeoutError::Disconnected),
+        };
+        match received {
+            Ok(d) => {
+                pending.insert(d);
+            }
+            Err(RecvTimeoutError::Timeout) => {
+                let now = Instant::now();
+                pending.retain(|d| *d > now);
+                let event = ClientMessageEvent::new(32, window, AtomEnum::NONE, [0u32; 5]);
+                let sent = conn.send_event(false, window, EventMask::NO_EVENT, event);
+                if sent.is_err() || conn.flush().is_err() {
+                    return;
+                }
+            }
+            Err(RecvTimeoutError::Disconnected) => return,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    #[test]
+    fn deadlines_are_sent_once() {
+        let (deadlines, receiver) = mpsc::channel();
+        let mut t = Timer {
+            deadlines,
+            armed: None,
+        };
+        let now = Instant::now();
+        t.wake_at(Some(now));
+        t.wake_at(Some(now));
+        t.wake_at(None);
+        t.wake_at(Some(now + Duration::from_secs(1)));
+        assert_eq!(
+            receiver.try_iter().collect::<Vec<_>>(),
+            vec![now, now + Duration::from_secs(1)]
+        );
+    }
+}
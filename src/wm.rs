@@ -1,13 +1,20 @@
 use crate::{
     commands,
     config::Config,
+    ewmh,
+    focus::{Crossings, FocusMode},
+    icccm::{self, FocusModel, WmClass, WmHints},
     keys,
-    layout::{self, Rect},
-    state::{Client, WmState},
+    kill::{self, Kills},
+    layout::{self, Direction, Edges, Gaps, Layout, Rect},
+    ping::Pings,
+    state::{Client, Layer, NetState, WindowType, WmState},
+    tabs::TabBars,
+    timer::Timer,
 };
 use anyhow::{Context, Result};
 use log::{debug, info, warn};
-use std::{collections::HashSet, env, process::Command};
+use std::{collections::HashSet, env, process::Command, time::Instant};
 use x11rb::wrapper::ConnectionExt as _;
 use x11rb::{
     connection::Connection,
@@ -21,6 +28,12 @@ struct Atoms {
     wm_protocols: Atom,
     wm_delete: Atom,
     wm_take_focus: Atom,
+    net_wm_ping: Atom,
+    net_wm_pid: Atom,
+    net_wm_user_time: Atom,
+    net_wm_user_time_window: Atom,
+    wm_state: Atom,
+    wm_change_state: Atom,
     net_supported: Atom,
     net_supporting_wm_check: Atom,
     net_wm_name: Atom,
@@ -30,12 +43,35 @@ struct Atoms {
     net_client_list_stacking: Atom,
     net_wm_state: Atom,
     net_wm_state_fullscreen: Atom,
+    net_wm_state_hidden: Atom,
+    net_wm_state_sticky: Atom,
+    net_wm_state_above: Atom,
+    net_wm_state_below: Atom,
+    net_wm_state_maximized_vert: Atom,
+    net_wm_state_maximized_horz: Atom,
+    net_wm_state_demands_attention: Atom,
+    net_wm_state_modal: Atom,
+    net_wm_state_skip_taskbar: Atom,
+    net_wm_state_skip_pager: Atom,
     net_wm_window_type: Atom,
+    net_wm_window_type_normal: Atom,
     net_wm_window_type_dialog: Atom,
+    net_wm_window_type_desktop: Atom,
+    net_wm_window_type_dock: Atom,
+    net_wm_window_type_splash: Atom,
+    net_wm_window_type_utility: Atom,
+    net_wm_window_type_toolbar: Atom,
+    net_wm_window_type_menu: Atom,
+    net_wm_window_type_notification: Atom,
     net_number_of_desktops: Atom,
     net_current_desktop: Atom,
     net_wm_desktop: Atom,
     net_desktop_names: Atom,
+    net_close_window: Atom,
+    net_moveresize_window: Atom,
+    net_wm_moveresize: Atom,
+    net_frame_extents: Atom,
+    net_request_frame_extents: Atom,
 }
 
 impl Atoms {
@@ -47,6 +83,12 @@ impl Atoms {
             wm_protocols: atom(conn, b"WM_PROTOCOLS")?,
             wm_delete: atom(conn, b"WM_DELETE_WINDOW")?,
             wm_take_focus: atom(conn, b"WM_TAKE_FOCUS")?,
+            net_wm_ping: atom(conn, b"_NET_WM_PING")?,
+            net_wm_pid: atom(conn, b"_NET_WM_PID")?,
+            net_wm_user_time: atom(conn, b"_NET_WM_USER_TIME")?,
+            net_wm_user_time_window: atom(conn, b"_NET_WM_USER_TIME_WINDOW")?,
+            wm_state: atom(conn, b"WM_STATE")?,
+            wm_change_state: atom(conn, b"WM_CHANGE_STATE")?,
             net_supported: atom(conn, b"_NET_SUPPORTED")?,
             net_supporting_wm_check: atom(conn, b"_NET_SUPPORTING_WM_CHECK")?,
             net_wm_name: atom(conn, b"_NET_WM_NAME")?,
@@ -56,16 +98,75 @@ impl Atoms {
             net_client_list_stacking: atom(conn, b"_NET_CLIENT_LIST_STACKING")?,
             net_wm_state: atom(conn, b"_NET_WM_STATE")?,
             net_wm_state_fullscreen: atom(conn, b"_NET_WM_STATE_FULLSCREEN")?,
+            net_wm_state_hidden: atom(conn, b"_NET_WM_STATE_HIDDEN")?,
+            net_wm_state_sticky: atom(conn, b"_NET_WM_STATE_STICKY")?,
+            net_wm_state_above: atom(conn, b"_NET_WM_STATE_ABOVE")?,
+            net_wm_state_below: atom(conn, b"_NET_WM_STATE_BELOW")?,
+            net_wm_state_maximized_vert: atom(conn, b"_NET_WM_STATE_MAXIMIZED_VERT")?,
+            net_wm_state_maximized_horz: atom(conn, b"_NET_WM_STATE_MAXIMIZED_HORZ")?,
+            net_wm_state_demands_attention: atom(conn, b"_NET_WM_STATE_DEMANDS_ATTENTION")?,
+            net_wm_state_modal: atom(conn, b"_NET_WM_STATE_MODAL")?,
+            net_wm_state_skip_taskbar: atom(conn, b"_NET_WM_STATE_SKIP_TASKBAR")?,
+            net_wm_state_skip_pager: atom(conn, b"_NET_WM_STATE_SKIP_PAGER")?,
             net_wm_window_type: atom(conn, b"_NET_WM_WINDOW_TYPE")?,
+            net_wm_window_type_normal: atom(conn, b"_NET_WM_WINDOW_TYPE_NORMAL")?,
             net_wm_window_type_dialog: atom(conn, b"_NET_WM_WINDOW_TYPE_DIALOG")?,
+            net_wm_window_type_desktop: atom(conn, b"_NET_WM_WINDOW_TYPE_DESKTOP")?,
+            net_wm_window_type_dock: atom(conn, b"_NET_WM_WINDOW_TYPE_DOCK")?,
+            net_wm_window_type_splash: atom(conn, b"_NET_WM_WINDOW_TYPE_SPLASH")?,
+            net_wm_window_type_utility: atom(conn, b"_NET_WM_WINDOW_TYPE_UTILITY")?,
+            net_wm_window_type_toolbar: atom(conn, b"_NET_WM_WINDOW_TYPE_TOOLBAR")?,
+            net_wm_window_type_menu: atom(conn, b"_NET_WM_WINDOW_TYPE_MENU")?,
+            net_wm_window_type_notification: atom(conn, b"_NET_WM_WINDOW_TYPE_NOTIFICATION")?,
             net_number_of_desktops: atom(conn, b"_NET_NUMBER_OF_DESKTOPS")?,
             net_current_desktop: atom(conn, b"_NET_CURRENT_DESKTOP")?,
             net_wm_desktop: atom(conn, b"_NET_WM_DESKTOP")?,
             net_desktop_names: atom(conn, b"_NET_DESKTOP_NAMES")?,
+            net_close_window: atom(conn, b"_NET_CLOSE_WINDOW")?,
+            net_moveresize_window: atom(conn, b"_NET_MOVERESIZE_WINDOW")?,
+            net_wm_moveresize: atom(conn, b"_NET_WM_MOVERESIZE")?,
+            net_frame_extents: atom(conn, b"_NET_FRAME_EXTENTS")?,
+            net_request_frame_extents: atom(conn, b"_NET_REQUEST_FRAME_EXTENTS")?,
         })
     }
-    fn supported(self) -> [Atom; 13] {
+    /// `_NET_WM_STATE` atoms and the client state each one names.
+    fn net_states(self) -> [(Atom, NetState); 11] {
         [
+            (self.net_wm_state_fullscreen, NetState::Fullscreen),
+            (self.net_wm_state_sticky, NetState::Sticky),
+            (self.net_wm_state_above, NetState::Above),
+            (self.net_wm_state_below, NetState::Below),
+            (self.net_wm_state_maximized_vert, NetState::MaximizedVert),
+            (self.net_wm_state_maximized_horz, NetState::MaximizedHorz),
+            (self.net_wm_state_hidden, NetState::Hidden),
+            (
+                self.net_wm_state_demands_attention,
+                NetState::DemandsAttention,
+            ),
+            (self.net_wm_state_modal, NetState::Modal),
+            (self.net_wm_state_skip_taskbar, NetState::SkipTaskbar),
+            (self.net_wm_state_skip_pager, NetState::SkipPager),
+        ]
+    }
+    /// `_NET_WM_WINDOW_TYPE` atoms and the class each one names.
+    fn window_types(self) -> [(Atom, WindowType); 9] {
+        [
+            (self.net_wm_window_type_normal, WindowType::Normal),
+            (self.net_wm_window_type_dialog, WindowType::Dialog),
+            (self.net_wm_window_type_desktop, WindowType::Desktop),
+            (self.net_wm_window_type_dock, WindowType::Dock),
+            (self.net_wm_window_type_splash, WindowType::Splash),
+            (self.net_wm_window_type_utility, WindowType::Utility),
+            (self.net_wm_window_type_toolbar, WindowType::Toolbar),
+            (self.net_wm_window_type_menu, WindowType::Menu),
+            (
+                self.net_wm_window_type_notification,
+                WindowType::Notification,
+            ),
+        ]
+    }
+    fn supported(self) -> Vec<Atom> {
+        let mut atoms = vec![
             self.net_supported,
             self.net_supporting_wm_check,
             self.net_wm_name,
@@ -73,16 +174,35 @@ impl Atoms {
             self.net_client_list,
             self.net_client_list_stacking,
             self.net_wm_state,
-            self.net_wm_state_fullscreen,
             self.net_wm_window_type,
             self.net_number_of_desktops,
             self.net_current_desktop,
             self.net_wm_desktop,
             self.net_desktop_names,
-        ]
+            self.net_close_window,
+            self.net_moveresize_window,
+            self.net_wm_moveresize,
+            self.net_frame_extents,
+            self.net_request_frame_extents,
+            self.net_wm_ping,
+            self.net_wm_user_time,
+            self.net_wm_user_time_window,
+        ];
+        atoms.extend(self.net_states().map(|(atom, _)| atom));
+        atoms.extend(self.window_types().map(|(atom, _)| atom));
+        atoms
     }
 }
 
+/// Pointer-driven move or resize started by `_NET_WM_MOVERESIZE`.
+struct Drag {
+    window: Window,
+    edges: Edges,
+    x: i16,
+    y: i16,
+    start: Rect,
+}
+
 struct Wm {
     conn: RustConnection,
     root: Window,
@@ -93,6 +213,15 @@ struct Wm {
     running: bool,
     restart: bool,
     ignored_unmaps: HashSet<Window>,
+    tabs: TabBars,
+    drag: Option<Drag>,
+    pings: Pings,
+    kills: Kills,
+    /// Server time of the latest user input BoringWM knows about.
+    last_input: u32,
+    crossings: Crossings,
+    /// Client last focused while click-to-focus, so without a button grab.
+    click_target: Option<Window>,
 }
 
 pub fn run() -> Result<()> {
@@ -126,6 +255,7 @@ pub fn run() -> Result<()> {
         screen.root_visual,
         &CreateWindowAux::new(),
     )?;
+    let tabs = TabBars::new(&conn, root).context("cannot initialize tab bars")?;
     let monitor = Rect {
         x: 0,
         y: 0,
@@ -137,11 +267,21 @@ pub fn run() -> Result<()> {
         root,
         atoms,
         config: config.clone(),
-        state: WmState::new(config.workspaces, vec![monitor], config.master_ratio),
+        state: WmState::new(
+            vec![monitor],
+            (0..config.workspaces).map(|ws| config.tiling(ws)).collect(),
+        ),
         check_window,
         running: true,
         restart: false,
         ignored_unmaps: HashSet::new(),
+        tabs,
+        drag: None,
+        pings: Pings::default(),
+        kills: Kills::default(),
+        last_input: 0,
+        crossings: Crossings::default(),
+        click_target: None,
     };
     wm.publish_root_properties()?;
     keys::grab_keys(&wm.conn, root)?;
@@ -154,11 +294,33 @@ pub fn run() -> Result<()> {
         "BoringWM started with {} workspace(s)",
         wm.state.workspace_count()
     );
+    let mut timer = Timer::spawn(check_window)?;
     while wm.running {
-        match wm.conn.wait_for_event() {
-            Ok(event) => wm.handle(event),
+        match wm.conn.poll_for_event() {
+            Ok(Some(event)) => {
+                wm.handle(event);
+                continue;
+            }
+            Ok(None) => {}
             Err(error) => return Err(error).context("X11 connection failed"),
         }
+        let now = Instant::now();
+        wm.expire_pings(now);
+        for (pid, start) in wm.kills.due(now) {
+            if kill::start_time(pid) == Some(start) {
+                debug!("grace period over, sending SIGKILL to {pid}");
+                kill::signal(pid, "KILL");
+            }
+        }
+        timer.wake_at(
+            [wm.pings.next_deadline(), wm.kills.next_deadline()]
+                .into_iter()
+                .flatten()
+                .min(),
+        );
+        wm.conn.flush().context("X11 connection failed")?;
+        let event = wm.conn.wait_for_event().context("X11 connection failed")?;
+        wm.handle(event);
     }
     wm.shutdown()?;
     if wm.restart {
@@ -242,6 +404,9 @@ impl Wm {
         Ok(())
     }
     fn handle(&mut self, event: Event) {
+        let ours = event
+            .wire_sequence_number()
+            .is_some_and(|s| self.crossings.ours(s));
         let result = match event {
             Event::MapRequest(e) => {
                 self.manage(e.window, false);
@@ -259,7 +424,15 @@ impl Wm {
             }
             Event::ConfigureRequest(e) => self.configure_request(e),
             Event::EnterNotify(e) => {
-                if self.state.contains(e.event) {
+                let deliberate =
+                    !ours && e.mode == NotifyMode::NORMAL && e.detail != NotifyDetail::INFERIOR;
+                if deliberate
+                    && self.config.focus_mode == FocusMode::FollowMouse
+                    && self
+                        .state
+                        .client(e.event)
+                        .is_some_and(|c| c.window_type.takes_focus())
+                {
                     self.state.set_focus(Some(e.event));
                     self.apply_focus()
                 }
@@ -269,14 +442,85 @@ impl Wm {
                 self.client_message(e);
                 Ok(())
             }
-            Event::PropertyNotify(e) => {
-                if self.state.contains(e.window) && e.atom == self.atoms.net_wm_state {
+            Event::PropertyNotify(e) if e.atom == self.atoms.net_wm_user_time => {
+                if let Some(t) = self.property_u32(e.window, e.atom, AtomEnum::CARDINAL) {
+                    self.note_input(t)
+                }
+                Ok(())
+            }
+            Event::PropertyNotify(e) if self.state.contains(e.window) => {
+                if e.atom == self.atoms.net_wm_state {
                     self.read_fullscreen(e.window)
+                } else if e.atom == AtomEnum::WM_HINTS.into() {
+                    let hints = self.read_hints(e.window);
+                    if let Some(c) = self.state.client_mut(e.window) {
+                        c.urgent = hints.urgent;
+                        c.accepts_input = hints.accepts_input();
+                    }
+                    self.attend(e.window);
+                    self.write_net_wm_state(e.window);
+                    self.paint_borders();
+                    let _ = self.conn.flush();
+                } else if e.atom == self.atoms.net_wm_name || e.atom == AtomEnum::WM_NAME.into() {
+                    let title = self.read_title(e.window);
+                    if let Some(c) = self.state.client_mut(e.window) {
+                        c.title = title;
+                    }
+                    self.draw_tabs()
+                }
+                Ok(())
+            }
+            Event::Expose(e) => {
+                if e.count == 0 && self.tabs.owns(e.window) {
+                    self.draw_tabs()
+                }
+                Ok(())
+            }
+            Event::MotionNotify(e) => {
+                self.drag_to(e.root_x, e.root_y);
+                Ok(())
+            }
+            Event::ButtonRelease(_) => {
+                self.end_drag();
+                Ok(())
+            }
+            Event::ButtonPress(e) => {
+                self.note_input(e.time);
+                if let Some(w) = self.tabs.hit(e.event, e.event_x, e.event_y) {
+                    self.state.set_focus(Some(w));
+                    self.apply_focus()
+                } else {
+                    if self.config.focus_mode == FocusMode::Click
+                        && self
+                            .state
+                            .client(e.event)
+                            .is_some_and(|c| c.window_type.takes_focus())
+                        && self.state.focused != Some(e.event)
+                    {
+                        self.state.set_focus(Some(e.event));
+                        self.apply_focus()
+                    }
+                    // Release the synchronous grab even when the press
+                    // focuses nothing, or the pointer stays frozen.
+                    let _ = self.conn.allow_events(Allow::REPLAY_POINTER, e.time);
+                }
+                Ok(())
+            }
+            Event::KeyRelease(e) if keys::MOD_KEYS.contains(&e.detail) => {
+                if self.state.cycling() {
+                    self.end_cycle()
                 }
                 Ok(())
             }
             Event::KeyPress(e) => {
+                self.note_input(e.time);
+                let before = (self.state.focused, self.state.current_workspace);
                 self.key(e.detail, keys::normalized(e.state));
+                if self.config.warp_pointer
+                    && (self.state.focused, self.state.current_workspace) != before
+                {
+                    self.warp_pointer(before.0)
+                }
                 Ok(())
             }
             _ => Ok(()),
@@ -287,7 +531,9 @@ impl Wm {
     }
     fn manage(&mut self, w: Window, existing: bool) {
         if self.state.contains(w) {
-            if !existing {
+            if self.state.client(w).is_some_and(|c| c.minimized) {
+                self.restore(w)
+            } else if !existing {
                 let _ = self.conn.map_window(w);
             }
             return;
@@ -327,15 +573,32 @@ impl Wm {
             .ok()
             .and_then(|c| c.reply().ok())
             .and_then(|p| p.value32()?.next());
-        let dialog = self
-            .property_atoms(w, self.atoms.net_wm_window_type)
-            .contains(&self.atoms.net_wm_window_type_dialog);
-        let fullscreen = self
-            .property_atoms(w, self.atoms.net_wm_state)
-            .contains(&self.atoms.net_wm_state_fullscreen);
+        let window_type = self.read_window_type(w);
+        let states = self.property_atoms(w, self.atoms.net_wm_state);
+        let fullscreen = states.contains(&self.atoms.net_wm_state_fullscreen);
+        let modal = states.contains(&self.atoms.net_wm_state_modal);
+        let hints = self.read_hints(w);
         let monitor = self.monitor_for(geometry);
-        let floating = transient.is_some() || dialog;
-        let client = Client {
+        let class = self.read_class(w);
+        let scratchpad = (0..self.config.scratchpads.len()).find(|i| {
+            class.matches(&self.config.scratchpads[*i].instance)
+                && self.state.scratchpad(*i).is_none()
+        });
+        let geometry = if scratchpad.is_some() {
+            self.scratchpad_rect(monitor)
+        } else {
+            geometry
+        };
+        let floating = window_type.floats()
+            || (transient.is_some() && window_type.arranged())
+            || modal
+            || scratchpad.is_some();
+        let border_width = if fullscreen || !window_type.arranged() {
+            0
+        } else {
+            self.state.tiling().border_width
+        };
+        let mut client = Client {
             window: w,
             workspace: self.state.current_workspace,
             monitor,
@@ -344,10 +607,68 @@ impl Wm {
             geometry,
             saved_geometry: fullscreen.then_some(geometry),
             saved_floating: floating,
+            cfact: 1.0,
+            title: self.read_title(w),
+            border_width,
+            scratchpad,
+            minimized: false,
+            sticky: false,
+            above: false,
+            below: false,
+            maximized_vert: false,
+            maximized_horz: false,
+            saved_maximized: None,
+            demands_attention: false,
+            modal: false,
+            skip_taskbar: false,
+            skip_pager: false,
+            urgent: hints.urgent,
+            accepts_input: hints.accepts_input(),
+            window_type,
+            unresponsive: false,
         };
+        for (atom, s) in self.atoms.net_states() {
+            if !matches!(s, NetState::Fullscreen | NetState::Hidden) && states.contains(&atom) {
+                client.set(s, true);
+            }
+        }
+        if client.maximized() {
+            client.saved_maximized = Some(geometry);
+        }
+        let previous = self.state.focused;
+        let user_time_window = self
+            .property_u32(w, self.atoms.net_wm_user_time_window, AtomEnum::WINDOW)
+            .unwrap_or(w);
+        let steals = !existing
+            && previous.is_some()
+            && transient != previous
+            && !ewmh::may_focus(
+                self.config.focus_stealing,
+                ewmh::Source::Application,
+                self.property_u32(
+                    user_time_window,
+                    self.atoms.net_wm_user_time,
+                    AtomEnum::CARDINAL,
+                ),
+                self.last_input,
+            );
         if !self.state.add(client) {
             return;
         }
+        if steals && self.state.focused == Some(w) {
+            debug!("window {w:#x} mapped without recent user input, not focusing");
+            self.state.set_focus(previous);
+            if let Some(c) = self.state.client_mut(w) {
+                c.demands_attention = true;
+            }
+        }
+        self.attend(w);
+        if user_time_window != w {
+            let _ = self.conn.change_window_attributes(
+                user_time_window,
+                &ChangeWindowAttributesAux::new().event_mask(EventMask::PROPERTY_CHANGE),
+            );
+        }
         let mask = EventMask::ENTER_WINDOW
             | EventMask::FOCUS_CHANGE
             | EventMask::PROPERTY_CHANGE
@@ -358,21 +679,22 @@ impl Wm {
                 .border_pixel(self.config.unfocused_border)
                 .event_mask(mask),
         );
-        let _ = self.conn.configure_window(
-            w,
-            &ConfigureWindowAux::new().border_width(if fullscreen {
-                0
-            } else {
-                self.config.border_width
-            }),
-        );
-        let _ = self.conn.change_property32(
-            PropMode::REPLACE,
-            w,
-            self.atoms.net_wm_desktop,
-            AtomEnum::CARDINAL,
-            &[self.state.current_workspace as u32],
-        );
+        let mut aux = ConfigureWindowAux::new().border_width(border_width);
+        if scratchpad.is_some() {
+            aux = aux
+                .x(geometry.x)
+                .y(geometry.y)
+                .width(geometry.width)
+                .height(geometry.height);
+        }
+        let _ = self.conn.configure_window(w, &aux);
+        if self.config.focus_mode == FocusMode::Click {
+            self.grab_buttons(w);
+        }
+        self.write_frame_extents(w, border_width);
+        self.write_desktop(w);
+        self.write_net_wm_state(w);
+        self.update_wm_state(w);
         let _ = self.conn.map_window(w);
         self.arrange();
         self.apply_focus();
@@ -381,6 +703,15 @@ impl Wm {
     }
     fn unmanage(&mut self, w: Window) {
         if self.state.remove(w).is_some() {
+            if self.drag.as_ref().is_some_and(|d| d.window == w) {
+                self.end_drag();
+            }
+            self.pings.forget(w);
+            if self.click_target == Some(w) {
+                self.click_target = None;
+            }
+            let _ = self.conn.ungrab_button(ButtonIndex::ANY, w, ModMask::ANY);
+            let _ = self.conn.delete_property(w, self.atoms.wm_state);
             debug!("unmanaged window {w:#x}");
             self.arrange();
             self.apply_focus();
@@ -388,7 +719,12 @@ impl Wm {
         }
     }
     fn configure_request(&self, e: ConfigureRequestEvent) -> Result<()> {
-        if !self.state.contains(e.window) || self.state.client(e.window).is_some_and(|c| c.floating)
+        let managed = self.state.contains(e.window);
+        if !managed
+            || self
+                .state
+                .client(e.window)
+                .is_some_and(|c| c.floating || !c.window_type.arranged())
         {
             let mut a = ConfigureWindowAux::new();
             if e.value_mask.contains(ConfigWindow::X) {
@@ -406,10 +742,11 @@ impl Wm {
             if e.value_mask.contains(ConfigWindow::BORDER_WIDTH) {
                 a = a.border_width(e.border_width as u32)
             }
-            if e.value_mask.contains(ConfigWindow::SIBLING) {
+            // Managed clients are stacked by layer in `restack`.
+            if !managed && e.value_mask.contains(ConfigWindow::SIBLING) {
                 a = a.sibling(e.sibling)
             }
-            if e.value_mask.contains(ConfigWindow::STACK_MODE) {
+            if !managed && e.value_mask.contains(ConfigWindow::STACK_MODE) {
                 a = a.stack_mode(e.stack_mode)
             }
             self.conn.configure_window(e.window, &a)?;
@@ -419,18 +756,39 @@ impl Wm {
         Ok(())
     }
     fn arrange(&mut self) {
+        let tiling = self.state.tiling();
+        let borders = self
+            .state
+            .visible()
+            .into_iter()
+            .filter_map(|w| self.state.client(w).map(|c| (w, c.border_width)))
+            .collect::<Vec<_>>();
         for monitor in 0..self.state.monitors.len() {
             let ids = self.state.tiled_on(monitor);
-            let rects = layout::master_stack(
-                self.state.monitors[monitor],
-                ids.len(),
-                self.config.gaps,
-                self.config.border_width,
-                self.state.master_ratio,
+            let area = self.state.monitors[monitor];
+            let alone = ids.len() == 1;
+            let gaps = if !tiling.gaps_enabled || (alone && self.config.smart_gaps) {
+                Gaps::default()
+            } else {
+                tiling.gaps
+            };
+            let border = if alone && self.config.smart_borders {
+                0
+            } else {
+                tiling.border_width
+            };
+            let rects = tiling.rects(area, &self.state.weights(&ids), gaps, border);
+            self.tabs.place(
+                &self.conn,
+                monitor,
+                tiling.tab_bar(area, ids.len(), gaps.outer),
+                tiling.layout == Layout::Stacked,
+                ids.clone(),
             );
             for (w, r) in ids.into_iter().zip(rects) {
                 if let Some(c) = self.state.client_mut(w) {
-                    c.geometry = r
+                    c.geometry = r;
+                    c.border_width = border;
                 }
                 let _ = self.conn.configure_window(
                     w,
@@ -439,33 +797,173 @@ impl Wm {
                         .y(r.y)
                         .width(r.width)
                         .height(r.height)
-                        .border_width(self.config.border_width),
+                        .border_width(border),
                 );
             }
         }
+        let monitors = self.state.monitors.clone();
         for w in self.state.visible() {
-            if let Some(c) = self.state.client(w) {
-                if c.fullscreen {
-                    let r = self.state.monitors[c.monitor];
-                    let _ = self.conn.configure_window(
-                        w,
-                        &ConfigureWindowAux::new()
-                            .x(r.x)
-                            .y(r.y)
-                            .width(r.width)
-                            .height(r.height)
-                            .border_width(0)
-                            .stack_mode(StackMode::ABOVE),
-                    );
-                }
+            let Some(c) = self.state.client_mut(w) else {
+                continue;
+            };
+            if c.fullscreen {
+                c.border_width = 0;
+                let r = monitors[c.monitor];
+                let _ = self.conn.configure_window(
+                    w,
+                    &ConfigureWindowAux::new()
+                        .x(r.x)
+                        .y(r.y)
+                        .width(r.width)
+                        .height(r.height)
+                        .border_width(0),
+                );
+            } else if c.maximized() {
+                c.border_width = tiling.border_width;
+                let r = layout::maximized(
+                    monitors[c.monitor],
+                    c.geometry,
+                    c.maximized_horz,
+                    c.maximized_vert,
+                    c.border_width,
+                );
+                c.geometry = r;
+                let _ = self.conn.configure_window(
+                    w,
+                    &ConfigureWindowAux::new()
+                        .x(r.x)
+                        .y(r.y)
+                        .width(r.width)
+                        .height(r.height)
+                        .border_width(c.border_width),
+                );
+            } else if c.floating && c.border_width != tiling.border_width {
+                c.border_width = tiling.border_width;
+                let _ = self
+                    .conn
+                    .configure_window(w, &ConfigureWindowAux::new().border_width(c.border_width));
+            }
+        }
+        for (w, old) in borders {
+            match self.state.client(w) {
+                Some(c) if c.border_width != old => self.write_frame_extents(w, c.border_width),
+                _ => {}
+            }
+        }
+        self.draw_tabs();
+        self.expect_crossings();
+        let _ = self.conn.flush();
+    }
+    /// Mod+Tab: walk the recently used clients of this workspace while the
+    /// keyboard is grabbed, so the release of Mod ends the cycle.
+    fn cycle_recent(&mut self, delta: isize) {
+        let started = self.state.cycling();
+        if !self.state.cycle_recent(delta) {
+            return;
+        }
+        if !started {
+            let grabbed = self
+                .conn
+                .grab_keyboard(
+                    false,
+                    self.root,
+                    CURRENT_TIME,
+                    GrabMode::ASYNC,
+                    GrabMode::ASYNC,
+                )
+                .ok()
+                .and_then(|c| c.reply().ok())
+                .is_some_and(|r| r.status == GrabStatus::SUCCESS);
+            // Mod may already be up by the time the grab is active.
+            let held = self
+                .conn
+                .query_pointer(self.root)
+                .ok()
+                .and_then(|c| c.reply().ok())
+                .is_some_and(|p| keys::normalized(p.mask).contains(keys::MOD));
+            if !grabbed || !held {
+                self.apply_focus();
+                self.end_cycle();
+                return;
             }
         }
+        self.apply_focus()
+    }
+    fn end_cycle(&mut self) {
+        self.state.end_cycle();
+        let _ = self.conn.ungrab_keyboard(CURRENT_TIME);
         let _ = self.conn.flush();
     }
-    fn apply_focus(&self) {
+    /// Move the pointer to the middle of the focused client unless it is
+    /// already inside. With nothing focused, move it to the monitor of
+    /// `previous`, the client focused before, or leave it where it is.
+    fn warp_pointer(&mut self, previous: Option<Window>) {
+        let target = match self.state.focused.and_then(|w| self.state.client(w)) {
+            Some(c) => Rect {
+                width: c.geometry.width + 2 * c.border_width,
+                height: c.geometry.height + 2 * c.border_width,
+                ..c.geometry
+            },
+            None => match previous
+                .and_then(|w| self.state.client(w))
+                .and_then(|c| self.state.monitors.get(self.monitor_for(c.geometry)))
+            {
+                Some(m) => *m,
+                None => return,
+            },
+        };
+        let Some(pointer) = self
+            .conn
+            .query_pointer(self.root)
+            .ok()
+            .and_then(|c| c.reply().ok())
+        else {
+            return;
+        };
+        if target.contains(pointer.root_x.into(), pointer.root_y.into()) {
+            return;
+        }
+        let (x, y) = target.center();
+        let _ = self
+            .conn
+            .warp_pointer(x11rb::NONE, self.root, 0, 0, 0, 0, x as i16, y as i16);
+        self.expect_crossings();
+        let _ = self.conn.flush();
+    }
+    /// Ignore pointer crossings caused by the requests sent so far.
+    fn expect_crossings(&mut self) {
+        if let Ok(cookie) = self.conn.no_operation() {
+            self.crossings.expect(cookie.sequence_number());
+        }
+    }
+    /// Publish `_NET_FRAME_EXTENTS`: BoringWM's only frame is the border.
+    fn write_frame_extents(&self, w: Window, border: u32) {
+        let _ = self.conn.change_property32(
+            PropMode::REPLACE,
+            w,
+            self.atoms.net_frame_extents,
+            AtomEnum::CARDINAL,
+            &[border; 4],
+        );
+    }
+    fn draw_tabs(&self) {
+        self.tabs.draw(
+            &self.conn,
+            (self.config.focused_border, self.config.unfocused_border),
+            |w| {
+                let title = self.state.client(w).map(|c| c.title.clone());
+                (title.unwrap_or_default(), Some(w) == self.state.focused)
+            },
+        );
+    }
+    fn paint_borders(&self) {
         for w in self.state.visible() {
-            let color = if Some(w) == self.state.focused {
+            let color = if self.state.client(w).is_some_and(|c| c.unresponsive) {
+                self.config.unresponsive_border
+            } else if Some(w) == self.state.focused {
                 self.config.focused_border
+            } else if self.state.client(w).is_some_and(|c| c.needs_attention()) {
+                self.config.urgent_border
             } else {
                 self.config.unfocused_border
             };
@@ -473,19 +971,40 @@ impl Wm {
                 .conn
                 .change_window_attributes(w, &ChangeWindowAttributesAux::new().border_pixel(color));
         }
+    }
+    fn apply_focus(&mut self) {
+        self.state.remember_focus();
+        if let Some(w) = self.state.focused {
+            if self.attend(w) {
+                self.write_net_wm_state(w);
+            }
+        }
+        self.paint_borders();
         let target = self.state.focused.unwrap_or(self.root);
-        let _ = self
-            .conn
-            .set_input_focus(InputFocus::POINTER_ROOT, target, CURRENT_TIME);
+        let protocols = if target == self.root {
+            Vec::new()
+        } else {
+            self.property_atoms(target, self.atoms.wm_protocols)
+        };
+        let model = if target == self.root {
+            FocusModel::Passive
+        } else {
+            FocusModel::new(
+                self.state.client(target).map_or(true, |c| c.accepts_input),
+                protocols.contains(&self.atoms.wm_take_focus),
+            )
+        };
+        if protocols.contains(&self.atoms.net_wm_ping) && self.pings.due(target, Instant::now()) {
+            self.ping(target);
+        }
+        if model.set_input_focus() {
+            let _ = self
+                .conn
+                .set_input_focus(InputFocus::POINTER_ROOT, target, CURRENT_TIME);
+        }
         if target != self.root {
-            let _ = self.conn.configure_window(
-                target,
-                &ConfigureWindowAux::new().stack_mode(StackMode::ABOVE),
-            );
-            if self
-                .property_atoms(target, self.atoms.wm_protocols)
-                .contains(&self.atoms.wm_take_focus)
-            {
+            self.state.raise(target);
+            if model.take_focus() {
                 let event = ClientMessageEvent::new(
                     32,
                     target,
@@ -497,7 +1016,76 @@ impl Wm {
                     .send_event(false, target, EventMask::NO_EVENT, event);
             }
         }
+        self.restack();
+        self.draw_tabs();
         self.sync_properties();
+        self.grab_click();
+        self.expect_crossings();
+    }
+    /// With click-to-focus, hold a passive button grab on every client but
+    /// the focused one; the press that focuses a client is replayed to it.
+    fn grab_click(&mut self) {
+        if self.config.focus_mode != FocusMode::Click || self.click_target == self.state.focused {
+            return;
+        }
+        if let Some(w) = self.click_target.filter(|w| self.state.contains(*w)) {
+            self.grab_buttons(w);
+        }
+        if let Some(w) = self.state.focused {
+            let _ = self.conn.ungrab_button(ButtonIndex::ANY, w, ModMask::ANY);
+        }
+        self.click_target = self.state.focused;
+    }
+    /// Grab the buttons on `w` unless it is a window that never takes focus
+    /// and so would keep the grab for good.
+    fn grab_buttons(&self, w: Window) {
+        if !self
+            .state
+            .client(w)
+            .is_some_and(|c| c.window_type.takes_focus())
+        {
+            return;
+        }
+        let _ = self.conn.grab_button(
+            false,
+            w,
+            EventMask::BUTTON_PRESS,
+            GrabMode::SYNC,
+            GrabMode::ASYNC,
+            x11rb::NONE,
+            x11rb::NONE,
+            ButtonIndex::ANY,
+            ModMask::ANY,
+        );
+    }
+    /// Stack the mapped clients bottom to top as `WmState::stacking` orders
+    /// them, each directly above the previous, with tab bars over the tiles.
+    fn restack(&self) {
+        let stack = self
+            .state
+            .stacking()
+            .into_iter()
+            .filter(|w| self.state.is_shown(*w))
+            .collect::<Vec<_>>();
+        let mut below = None;
+        for w in &stack {
+            let aux = match below {
+                Some(sibling) => ConfigureWindowAux::new()
+                    .sibling(sibling)
+                    .stack_mode(StackMode::ABOVE),
+                None => ConfigureWindowAux::new().stack_mode(StackMode::BELOW),
+            };
+            let _ = self.conn.configure_window(*w, &aux);
+            below = Some(*w);
+        }
+        let top_tile = stack.iter().rev().find(|w| {
+            self.state
+                .client(**w)
+                .is_some_and(|c| c.layer() == Layer::Tiled)
+        });
+        if let Some(w) = top_tile {
+            self.tabs.stack_above(&self.conn, *w);
+        }
     }
     fn key(&mut self, key: u8, mods: ModMask) {
         if !mods.contains(keys::MOD) {
@@ -505,10 +1093,14 @@ impl Wm {
         }
         let shift = mods.contains(keys::SHIFT);
         if let Some(ws) = keys::DIGITS.iter().position(|k| *k == key) {
-            if shift {
+            if mods.contains(keys::CONTROL) {
+                self.toggle_scratchpad(ws)
+            } else if shift {
                 if let Some(w) = self.state.move_focused_to_workspace(ws) {
                     self.ignored_unmaps.insert(w);
                     let _ = self.conn.unmap_window(w);
+                    self.write_desktop(w);
+                    self.update_wm_state(w);
                     self.arrange();
                     self.apply_focus();
                     self.sync_properties();
@@ -518,12 +1110,66 @@ impl Wm {
             }
             return;
         }
+        let direction = match key {
+            keys::KEY_LEFT => Some(Direction::Left),
+            keys::KEY_RIGHT => Some(Direction::Right),
+            keys::KEY_UP => Some(Direction::Up),
+            keys::KEY_DOWN => Some(Direction::Down),
+            _ => None,
+        };
+        if let Some(direction) = direction {
+            if shift {
+                if self.state.swap_direction(direction) {
+                    self.arrange()
+                }
+            } else if self.state.focus_direction(direction) {
+                self.apply_focus()
+            }
+            return;
+        }
         match (key, shift) {
             (keys::KEY_RETURN, false) => self.spawn(self.config.terminal.clone()),
             (keys::KEY_T, false) => self.spawn(self.config.file_manager.clone()),
             (keys::KEY_B, false) => self.spawn(self.config.browser.clone()),
             (keys::KEY_D, false) => self.spawn(self.config.launcher.clone()),
-            (keys::KEY_Q, false) => self.close_focused(),
+            (keys::KEY_Q, false) => {
+                if let Some(w) = self.state.focused {
+                    self.close(w)
+                }
+            }
+            (keys::KEY_Q, true) => {
+                if let Some(w) = self.state.focused {
+                    self.force_kill(w)
+                }
+            }
+            (keys::KEY_GRAVE, false) => self.toggle_scratchpad(0),
+            (keys::KEY_TAB, _) => self.cycle_recent(if shift { -1 } else { 1 }),
+            (keys::KEY_BACKSPACE, false) => {
+                if let Some(w) = self.state.previous_window() {
+                    self.activate(w)
+                }
+            }
+            (keys::KEY_N, false) => {
+                if let Some(w) = self.state.focused {
+                    self.minimize(w)
+                }
+            }
+            (keys::KEY_N, true) => {
+                if let Some(w) = self.state.last_minimized() {
+                    self.restore(w)
+                }
+            }
+            (keys::KEY_U, false) => self.spawn(self.config.window_picker.clone()),
+            (keys::KEY_A, false) => {
+                if let Some(w) = self.state.last_attention() {
+                    self.activate(w)
+                }
+            }
+            (keys::KEY_S, false) => {
+                if let Some(w) = self.state.focused {
+                    self.set_sticky(w, !self.state.client(w).is_some_and(|c| c.sticky))
+                }
+            }
             (keys::KEY_J, false) => {
                 self.state.focus_cycle(1);
                 self.apply_focus()
@@ -544,12 +1190,60 @@ impl Wm {
                 self.state.promote();
                 self.arrange()
             }
+            (keys::KEY_COMMA, false) => {
+                self.state.adjust_nmaster(-1);
+                self.arrange()
+            }
+            (keys::KEY_PERIOD, false) => {
+                self.state.adjust_nmaster(1);
+                self.arrange()
+            }
             (keys::KEY_H, false) => {
-                self.state.master_ratio = (self.state.master_ratio - 0.05).max(0.2);
+                self.state.adjust_master_ratio(-0.05);
                 self.arrange()
             }
             (keys::KEY_L, false) => {
-                self.state.master_ratio = (self.state.master_ratio + 0.05).min(0.8);
+                self.state.adjust_master_ratio(0.05);
+                self.arrange()
+            }
+            (keys::KEY_H, true) => {
+                self.state.adjust_cfact(Some(-0.25));
+                self.arrange()
+            }
+            (keys::KEY_L, true) => {
+                self.state.adjust_cfact(Some(0.25));
+                self.arrange()
+            }
+            (keys::KEY_O, true) => {
+                self.state.adjust_cfact(None);
+                self.arrange()
+            }
+            (keys::KEY_MINUS, false) => {
+                self.state.adjust_gaps(-2);
+                self.arrange()
+            }
+            (keys::KEY_EQUAL, false) => {
+                self.state.adjust_gaps(2);
+                self.arrange()
+            }
+            (keys::KEY_MINUS, true) => {
+                self.state.toggle_gaps();
+                self.arrange()
+            }
+            (keys::KEY_EQUAL, true) => {
+                self.state.reset_gaps();
+                self.arrange()
+            }
+            (keys::KEY_BRACKET_LEFT, false) => {
+                self.state.adjust_border(-1);
+                self.arrange()
+            }
+            (keys::KEY_BRACKET_RIGHT, false) => {
+                self.state.adjust_border(1);
+                self.arrange()
+            }
+            (keys::KEY_BRACKET_LEFT | keys::KEY_BRACKET_RIGHT, true) => {
+                self.state.reset_border();
                 self.arrange()
             }
             (keys::KEY_F, false) => {
@@ -565,6 +1259,11 @@ impl Wm {
                     self.arrange()
                 }
             }
+            (keys::KEY_SPACE, true) => {
+                self.state.cycle_layout();
+                debug!("layout {:?}", self.state.tiling().layout);
+                self.arrange()
+            }
             (keys::KEY_E, true) => self.running = false,
             (keys::KEY_R, true) => {
                 self.restart = true;
@@ -578,12 +1277,117 @@ impl Wm {
             warn!("{e:#}")
         }
     }
-    fn close_focused(&self) {
-        let Some(w) = self.state.focused else { return };
-        if self
-            .property_atoms(w, self.atoms.wm_protocols)
-            .contains(&self.atoms.wm_delete)
-        {
+    /// Spawn scratchpad `index` if its window does not exist yet, otherwise
+    /// hide it from or show it on the current workspace.
+    fn toggle_scratchpad(&mut self, index: usize) {
+        let Some(pad) = self
+            .config
+            .scratchpads
+            .get(index)
+            .filter(|p| !p.command.is_empty())
+        else {
+            return;
+        };
+        let Some(w) = self.state.scratchpad(index) else {
+            self.spawn(pad.command.clone());
+            return;
+        };
+        let here = self
+            .state
+            .client(w)
+            .is_some_and(|c| c.workspace == self.state.current_workspace);
+        if here {
+            self.ignored_unmaps.insert(w);
+            let _ = self.conn.unmap_window(w);
+            self.state.hide(w);
+            self.update_wm_state(w);
+            self.arrange();
+            self.apply_focus();
+            self.sync_properties();
+        } else {
+            self.show_scratchpad(w)
+        }
+    }
+    fn show_scratchpad(&mut self, w: Window) {
+        if !self.state.show(w) {
+            return;
+        }
+        let monitor = self.state.client(w).map_or(0, |c| c.monitor);
+        let r = self.scratchpad_rect(monitor);
+        if let Some(c) = self.state.client_mut(w) {
+            c.geometry = r;
+        }
+        let _ = self.conn.configure_window(
+            w,
+            &ConfigureWindowAux::new()
+                .x(r.x)
+                .y(r.y)
+                .width(r.width)
+                .height(r.height),
+        );
+        self.write_desktop(w);
+        self.update_wm_state(w);
+        let _ = self.conn.map_window(w);
+        self.arrange();
+        self.apply_focus();
+        self.sync_properties();
+    }
+    fn scratchpad_rect(&self, monitor: usize) -> Rect {
+        let area = self
+            .state
+            .monitors
+            .get(monitor)
+            .copied()
+            .unwrap_or_default();
+        layout::centered(area, area.width * 3 / 5, area.height / 2)
+    }
+    /// Iconify `w`: unmap it without unmanaging and mark it `IconicState`.
+    fn minimize(&mut self, w: Window) {
+        if !self.state.minimize(w) {
+            return;
+        }
+        self.ignored_unmaps.insert(w);
+        let _ = self.conn.unmap_window(w);
+        self.update_wm_state(w);
+        self.write_net_wm_state(w);
+        self.arrange();
+        self.apply_focus();
+        self.sync_properties();
+    }
+    fn restore(&mut self, w: Window) {
+        if !self.state.restore(w) {
+            return;
+        }
+        if self.state.is_shown(w) {
+            let _ = self.conn.map_window(w);
+        }
+        self.update_wm_state(w);
+        self.write_net_wm_state(w);
+        self.arrange();
+        self.apply_focus();
+        self.sync_properties();
+    }
+    /// Set `WM_STATE` to NormalState while `w` is mapped, else IconicState.
+    fn update_wm_state(&self, w: Window) {
+        let state = if self.state.is_shown(w) {
+            icccm::NORMAL_STATE
+        } else {
+            icccm::ICONIC_STATE
+        };
+        let _ = self.conn.change_property32(
+            PropMode::REPLACE,
+            w,
+            self.atoms.wm_state,
+            self.atoms.wm_state,
+            &[state, x11rb::NONE],
+        );
+    }
+    /// Ask `w` to close with `WM_DELETE_WINDOW`, or disconnect it when it
+    /// does not support that or stopped answering pings.
+    fn close(&mut self, w: Window) {
+        let protocols = self.property_atoms(w, self.atoms.wm_protocols);
+        let unresponsive = self.state.client(w).is_some_and(|c| c.unresponsive);
+        if protocols.contains(&self.atoms.wm_delete) && !unresponsive {
             let event = ClientMessageEvent::new(
                 32,
                 w,
@@ -591,38 +1395,349 @@ impl Wm {
                 [self.atoms.wm_delete, CURRENT_TIME, 0, 0, 0],
             );
             let _ = self.conn.send_event(false, w, EventMask::NO_EVENT, event);
+            if protocols.contains(&self.atoms.net_wm_ping) {
+                self.ping(w);
+            }
         } else if let Err(e) = self.conn.kill_client(w) {
             warn!("cannot close {w:#x}: {e}")
         }
     }
+    /// Disconnect `w` with `KillClient`; when its `_NET_WM_PID` belongs to
+    /// this host, also send the process `SIGTERM`, then `SIGKILL` after
+    /// [`kill::GRACE`] unless the pid no longer names the same process.
+    fn force_kill(&mut self, w: Window) {
+        let pid = self.local_pid(w);
+        let start = pid.and_then(kill::start_time);
+        if let Err(e) = self.conn.kill_client(w) {
+            warn!("cannot kill {w:#x}: {e}");
+        }
+        if let Some(pid) = pid {
+            debug!("sending SIGTERM to {pid} for window {w:#x}");
+            kill::signal(pid, "TERM");
+            if let Some(start) = start {
+                self.kills.schedule(pid, start, Instant::now());
+            }
+        }
+    }
+    /// `_NET_WM_PID` of `w` when `WM_CLIENT_MACHINE` names this host.
+    fn local_pid(&self, w: Window) -> Option<i32> {
+        let property = |name: Atom, kind: Atom| {
+            self.conn
+                .get_property(false, w, name, kind, 0, 64)
+                .ok()
+                .and_then(|c| c.reply().ok())
+        };
+        let machine = property(AtomEnum::WM_CLIENT_MACHINE.into(), AtomEnum::ANY.into())?;
+        let machine = String::from_utf8_lossy(&machine.value);
+        let host = kill::local_hostname()?;
+        if !kill::same_host(machine.trim_end_matches('\0'), &host) {
+            return None;
+        }
+        let pid = property(self.atoms.net_wm_pid, AtomEnum::CARDINAL.into())?
+            .value32()?
+            .next()?;
+        kill::target(pid, std::process::id())
+    }
+    /// Send `_NET_WM_PING` unless an earlier one is still unanswered.
+    fn ping(&mut self, w: Window) {
+        let Some(token) = self.pings.send(w, Instant::now()) else {
+            return;
+        };
+        let event = ClientMessageEvent::new(
+            32,
+            w,
+            self.atoms.wm_protocols,
+            [self.atoms.net_wm_ping, token, w, 0, 0],
+        );
+        let _ = self.conn.send_event(false, w, EventMask::NO_EVENT, event);
+    }
+    fn expire_pings(&mut self, now: Instant) {
+        let late = self.pings.expired(now);
+        if late.is_empty() {
+            return;
+        }
+        for w in late {
+            if let Some(c) = self.state.client_mut(w) {
+                warn!("window {w:#x} ({}) is not responding", c.title);
+                c.unresponsive = true;
+            }
+        }
+        self.paint_borders();
+        let _ = self.conn.flush();
+    }
     fn client_message(&mut self, e: ClientMessageEvent) {
-        if e.type_ == self.atoms.net_wm_state {
-            let d = e.data.as_data32();
-            if d[1] == self.atoms.net_wm_state_fullscreen
-                || d[2] == self.atoms.net_wm_state_fullscreen
-            {
-                let current = self.state.client(e.window).is_some_and(|c| c.fullscreen);
-                let wanted = match d[0] {
-                    0 => false,
-                    1 => true,
-                    2 => !current,
-                    _ => return,
-                };
-                self.set_fullscreen(e.window, wanted)
+        let d = e.data.as_data32();
+        if e.type_ == self.atoms.wm_protocols && d[0] == self.atoms.net_wm_ping {
+            if self.pings.pong(d[2], d[1]) {
+                if let Some(c) = self.state.client_mut(d[2]).filter(|c| c.unresponsive) {
+                    c.unresponsive = false;
+                    self.paint_borders();
+                }
+            }
+        } else if e.type_ == self.atoms.net_wm_state {
+            for atom in [d[1], d[2]] {
+                self.change_net_wm_state(e.window, atom, d[0])
+            }
+        } else if e.type_ == self.atoms.net_request_frame_extents {
+            if !self.state.contains(e.window) {
+                self.write_frame_extents(e.window, self.state.tiling().border_width);
+                let _ = self.conn.flush();
+            }
+        } else if e.type_ == self.atoms.net_close_window && self.state.contains(e.window) {
+            self.close(e.window)
+        } else if e.type_ == self.atoms.net_moveresize_window {
+            self.moveresize_window(e.window, d)
+        } else if e.type_ == self.atoms.net_wm_moveresize {
+            if d[2] == ewmh::MOVERESIZE_CANCEL {
+                self.end_drag()
+            } else if let Some(edges) = ewmh::moveresize_edges(d[2]) {
+                self.begin_drag(e.window, edges, d[0] as i16, d[1] as i16)
+            }
+        } else if e.type_ == self.atoms.wm_change_state {
+            if d[0] == icccm::ICONIC_STATE {
+                self.minimize(e.window)
             }
         } else if e.type_ == self.atoms.net_current_desktop {
-            self.switch_workspace(e.data.as_data32()[0] as usize)
-        } else if e.type_ == self.atoms.net_active_window && self.state.contains(e.window) {
-            let ws = self
-                .state
-                .client(e.window)
-                .map(|c| c.workspace)
-                .unwrap_or(0);
+            let (previous, workspace) = (self.state.focused, self.state.current_workspace);
+            self.switch_workspace(d[0] as usize);
+            if self.config.warp_pointer && self.state.current_workspace != workspace {
+                self.warp_pointer(previous)
+            }
+        } else if e.type_ == self.atoms.net_active_window {
+            let from_focused = d[2] != x11rb::NONE && self.state.focused == Some(d[2]);
+            let allowed = from_focused
+                || ewmh::may_focus(
+                    self.config.focus_stealing,
+                    ewmh::Source::new(d[0]),
+                    (d[1] != CURRENT_TIME).then_some(d[1]),
+                    self.last_input,
+                );
+            if allowed {
+                self.activate(e.window)
+            } else {
+                debug!("ignoring unsolicited activation of {:#x}", e.window);
+                self.demand_attention(e.window)
+            }
+        }
+    }
+    /// Programmatic move/resize; tiled clients keep their layout geometry.
+    fn moveresize_window(&mut self, w: Window, data: [u32; 5]) {
+        let Some(c) = self.state.client_mut(w) else {
+            return;
+        };
+        if !c.floating {
+            self.send_configure(w);
+            return;
+        }
+        let r = ewmh::moveresize_window(c.geometry, data);
+        c.geometry = r;
+        let _ = self.conn.configure_window(
+            w,
+            &ConfigureWindowAux::new()
+                .x(r.x)
+                .y(r.y)
+                .width(r.width)
+                .height(r.height),
+        );
+        let _ = self.conn.flush();
+    }
+    /// Grab the pointer to move or resize `w`, floating it once the grab
+    /// holds. Desktops, docks, and notifications are never dragged.
+    fn begin_drag(&mut self, w: Window, edges: Edges, x: i16, y: i16) {
+        let Some((was_tiled, start)) = self
+            .state
+            .client(w)
+            .filter(|c| !c.fullscreen && c.window_type.arranged())
+            .map(|c| (!c.floating, c.geometry))
+        else {
+            return;
+        };
+        let grabbed = self
+            .conn
+            .grab_pointer(
+                false,
+                self.root,
+                EventMask::BUTTON_RELEASE | EventMask::POINTER_MOTION,
+                GrabMode::ASYNC,
+                GrabMode::ASYNC,
+                x11rb::NONE,
+                x11rb::NONE,
+                CURRENT_TIME,
+            )
+            .ok()
+            .and_then(|c| c.reply().ok())
+            .is_some_and(|r| r.status == GrabStatus::SUCCESS);
+        if !grabbed {
+            debug!("cannot grab the pointer to drag {w:#x}");
+            return;
+        }
+        if let Some(c) = self.state.client_mut(w) {
+            c.floating = true;
+        }
+        self.drag = Some(Drag {
+            window: w,
+            edges,
+            x,
+            y,
+            start,
+        });
+        if was_tiled {
+            self.arrange();
+            self.apply_focus();
+        }
+    }
+    fn drag_to(&mut self, x: i16, y: i16) {
+        let Some(d) = &self.drag else {
+            return;
+        };
+        let w = d.window;
+        let r = layout::drag(
+            d.start,
+            d.edges,
+            i32::from(x) - i32::from(d.x),
+            i32::from(y) - i32::from(d.y),
+        );
+        if let Some(c) = self.state.client_mut(w) {
+            c.geometry = r;
+        }
+        let _ = self.conn.configure_window(
+            w,
+            &ConfigureWindowAux::new()
+                .x(r.x)
+                .y(r.y)
+                .width(r.width)
+                .height(r.height),
+        );
+        let _ = self.conn.flush();
+    }
+    fn end_drag(&mut self) {
+        if self.drag.take().is_some() {
+            let _ = self.conn.ungrab_pointer(CURRENT_TIME);
+            let _ = self.conn.flush();
+        }
+    }
+    /// Bring `w` to the screen, restoring and switching as needed, and focus it.
+    fn activate(&mut self, w: Window) {
+        if self.state.is_hidden(w) {
+            self.show_scratchpad(w)
+        } else if let Some(ws) = self.state.client(w).map(|c| c.workspace) {
+            self.restore(w);
             self.switch_workspace(ws);
-            self.state.set_focus(Some(e.window));
+            self.state.set_focus(Some(w));
             self.apply_focus()
         }
     }
+    /// Mark `w` as wanting attention in place of a refused focus request.
+    fn demand_attention(&mut self, w: Window) {
+        let Some(c) = self.state.client_mut(w) else {
+            return;
+        };
+        c.demands_attention = true;
+        self.attend(w);
+        self.write_net_wm_state(w);
+        self.paint_borders();
+        let _ = self.conn.flush();
+    }
+    /// Remember `time` as the latest user input unless it is older.
+    fn note_input(&mut self, time: u32) {
+        if time != CURRENT_TIME && ewmh::not_before(time, self.last_input) {
+            self.last_input = time;
+        }
+    }
+    /// Apply a `_NET_WM_STATE` remove (0), add (1) or toggle (2) request.
+    fn change_net_wm_state(&mut self, w: Window, atom: Atom, action: u32) {
+        let Some((_, state)) = self
+            .atoms
+            .net_states()
+            .into_iter()
+            .find(|(a, _)| *a == atom)
+        else {
+            return;
+        };
+        let Some(current) = self.state.client(w).map(|c| c.has(state)) else {
+            return;
+        };
+        let wanted = match action {
+            0 => false,
+            1 => true,
+            2 => !current,
+            _ => return,
+        };
+        if wanted == current {
+            return;
+        }
+        match state {
+            NetState::Fullscreen => self.set_fullscreen(w, wanted),
+            NetState::Sticky => self.set_sticky(w, wanted),
+            NetState::Hidden if wanted => self.minimize(w),
+            NetState::Hidden => self.restore(w),
+            NetState::MaximizedVert | NetState::MaximizedHorz => {
+                self.set_maximized(w, state, wanted)
+            }
+            _ => {
+                if let Some(c) = self.state.client_mut(w) {
+                    c.set(state, wanted);
+                    c.floating |= state == NetState::Modal && wanted;
+                }
+                self.attend(w);
+                self.write_net_wm_state(w);
+                self.arrange();
+                self.apply_focus();
+            }
+        }
+    }
+    fn set_maximized(&mut self, w: Window, axis: NetState, wanted: bool) {
+        let Some(c) = self.state.client_mut(w) else {
+            return;
+        };
+        let was = c.maximized();
+        c.set(axis, wanted);
+        if !was && c.maximized() {
+            c.saved_maximized = Some(c.geometry);
+        } else if was && !c.maximized() {
+            if let Some(r) = c.saved_maximized.take().filter(|_| c.floating) {
+                c.geometry = r;
+                let _ = self.conn.configure_window(
+                    w,
+                    &ConfigureWindowAux::new()
+                        .x(r.x)
+                        .y(r.y)
+                        .width(r.width)
+                        .height(r.height),
+                );
+            }
+        }
+        self.write_net_wm_state(w);
+        self.arrange();
+        self.apply_focus();
+    }
+    fn set_sticky(&mut self, w: Window, wanted: bool) {
+        let Some(c) = self.state.client_mut(w).filter(|c| c.sticky != wanted) else {
+            return;
+        };
+        c.sticky = wanted;
+        self.write_desktop(w);
+        self.write_net_wm_state(w);
+        let _ = self.conn.flush();
+    }
+    /// Publish `_NET_WM_DESKTOP`, 0xFFFFFFFF for clients on all workspaces.
+    fn write_desktop(&self, w: Window) {
+        let Some(c) = self.state.client(w) else {
+            return;
+        };
+        let desktop = if c.on_all_workspaces() {
+            0xFFFF_FFFF
+        } else {
+            c.workspace as u32
+        };
+        let _ = self.conn.change_property32(
+            PropMode::REPLACE,
+            w,
+            self.atoms.net_wm_desktop,
+            AtomEnum::CARDINAL,
+            &[desktop],
+        );
+    }
     fn read_fullscreen(&mut self, w: Window) {
         let wanted = self
             .property_atoms(w, self.atoms.net_wm_state)
@@ -647,11 +1762,22 @@ impl Wm {
                 c.geometry = r;
             }
         }
-        let values = if wanted {
-            vec![self.atoms.net_wm_state_fullscreen]
-        } else {
-            Vec::new()
+        self.write_net_wm_state(w);
+        self.arrange();
+        self.apply_focus();
+    }
+    /// Publish the `_NET_WM_STATE` atoms that BoringWM tracks for `w`.
+    fn write_net_wm_state(&self, w: Window) {
+        let Some(c) = self.state.client(w) else {
+            return;
         };
+        let values = self
+            .atoms
+            .net_states()
+            .into_iter()
+            .filter(|(_, s)| c.has(*s) || (*s == NetState::DemandsAttention && c.urgent))
+            .map(|(atom, _)| atom)
+            .collect::<Vec<_>>();
         let _ = self.conn.change_property32(
             PropMode::REPLACE,
             w,
@@ -659,20 +1785,19 @@ impl Wm {
             AtomEnum::ATOM,
             &values,
         );
-        self.arrange();
-        self.apply_focus();
     }
     fn switch_workspace(&mut self, ws: usize) {
         if !self.state.switch_workspace(ws) {
             return;
         }
         for c in self.state.clients() {
-            if c.workspace == ws {
+            if self.state.is_shown(c.window) {
                 let _ = self.conn.map_window(c.window);
             } else {
                 self.ignored_unmaps.insert(c.window);
                 let _ = self.conn.unmap_window(c.window);
             }
+            self.update_wm_state(c.window);
         }
         self.arrange();
         self.apply_focus();
@@ -686,6 +1811,80 @@ impl Wm {
             .and_then(|p| p.value32().map(Iterator::collect))
             .unwrap_or_default()
     }
+    /// First 32-bit item of a `CARDINAL` or `WINDOW` property.
+    fn property_u32(&self, w: Window, property: Atom, kind: AtomEnum) -> Option<u32> {
+        self.conn
+            .get_property(false, w, property, kind, 0, 1)
+            .ok()
+            .and_then(|c| c.reply().ok())
+            .and_then(|p| p.value32()?.next())
+    }
+    /// Window title from `_NET_WM_NAME`, falling back to Latin-1 `WM_NAME`.
+    fn read_title(&self, w: Window) -> String {
+        let property = |name: Atom, kind: Atom| {
+            self.conn
+                .get_property(false, w, name, kind, 0, 256)
+                .ok()
+                .and_then(|c| c.reply().ok())
+                .map(|p| p.value)
+                .filter(|v| !v.is_empty())
+        };
+        property(self.atoms.net_wm_name, self.atoms.utf8_string)
+            .map(|v| String::from_utf8_lossy(&v).into_owned())
+            .or_else(|| {
+                property(AtomEnum::WM_NAME.into(), AtomEnum::ANY.into())
+                    .map(|v| v.iter().map(|b| char::from(*b)).collect())
+            })
+            .unwrap_or_default()
+    }
+    /// First recognised `_NET_WM_WINDOW_TYPE`, else normal.
+    fn read_window_type(&self, w: Window) -> WindowType {
+        let types = self.atoms.window_types();
+        self.property_atoms(w, self.atoms.net_wm_window_type)
+            .into_iter()
+            .find_map(|atom| types.iter().find(|(a, _)| *a == atom).map(|(_, t)| *t))
+            .unwrap_or_default()
+    }
+    fn read_hints(&self, w: Window) -> WmHints {
+        self.conn
+            .get_property(false, w, AtomEnum::WM_HINTS, AtomEnum::WM_HINTS, 0, 9)
+            .ok()
+            .and_then(|c| c.reply().ok())
+            .and_then(|p| p.value32().map(|v| WmHints::parse(&v.collect::<Vec<_>>())))
+            .unwrap_or_default()
+    }
+    /// Refresh the attention of `w`; when focusing it cleared the urgency,
+    /// also clear the bit in its `WM_HINTS`. Returns whether it was cleared.
+    fn attend(&mut self, w: Window) -> bool {
+        let cleared = self.state.update_attention(w);
+        if cleared {
+            let value = self
+                .conn
+                .get_property(false, w, AtomEnum::WM_HINTS, AtomEnum::WM_HINTS, 0, 9)
+                .ok()
+                .and_then(|c| c.reply().ok())
+                .and_then(|p| p.value32().map(|v| v.collect::<Vec<_>>()))
+                .and_then(|v| icccm::without_urgency(&v));
+            if let Some(value) = value {
+                let _ = self.conn.change_property32(
+                    PropMode::REPLACE,
+                    w,
+                    AtomEnum::WM_HINTS,
+                    AtomEnum::WM_HINTS,
+                    &value,
+                );
+            }
+        }
+        cleared
+    }
+    fn read_class(&self, w: Window) -> WmClass {
+        self.conn
+            .get_property(false, w, AtomEnum::WM_CLASS, AtomEnum::STRING, 0, 256)
+            .ok()
+            .and_then(|c| c.reply().ok())
+            .map(|p| WmClass::parse(&p.value))
+            .unwrap_or_default()
+    }
     fn monitor_for(&self, r: Rect) -> usize {
         let x = r.x.saturating_add((r.width / 2) as i32);
         let y = r.y.saturating_add((r.height / 2) as i32);
@@ -707,7 +1906,7 @@ impl Wm {
                 y: c.geometry.y as i16,
                 width: c.geometry.width as u16,
                 height: c.geometry.height as u16,
-                border_width: self.config.border_width as u16,
+                border_width: c.border_width as u16,
                 override_redirect: false,
             };
             let _ = self
@@ -716,21 +1915,22 @@ impl Wm {
         }
     }
     fn sync_properties(&self) {
-        let clients = self.state.clients().map(|c| c.window).collect::<Vec<_>>();
+        let clients = self.state.client_list();
+        let stacking = self.state.stacking();
         let active = self.state.focused.into_iter().collect::<Vec<_>>();
         let _ = self.conn.change_property32(
             PropMode::REPLACE,
             self.root,
             self.atoms.net_client_list,
             AtomEnum::WINDOW,
-            &clients,
+            clients,
         );
         let _ = self.conn.change_property32(
             PropMode::REPLACE,
             self.root,
             self.atoms.net_client_list_stacking,
             AtomEnum::WINDOW,
-            &clients,
+            &stacking,
         );
         let _ = self.conn.change_property32(
             PropMode::REPLACE,
@@ -754,6 +1954,16 @@ impl Wm {
             let _ = self
                 .conn
                 .configure_window(c.window, &ConfigureWindowAux::new().border_width(0));
+            let _ = self.conn.change_property32(
+                PropMode::REPLACE,
+                c.window,
+                self.atoms.wm_state,
+                self.atoms.wm_state,
+                &[icccm::NORMAL_STATE, x11rb::NONE],
+            );
+            let _ = self
+                .conn
+                .delete_property(c.window, self.atoms.net_frame_extents);
         }
         for p in [
             self.atoms.net_active_window,
@@ -763,6 +1973,7 @@ impl Wm {
         ] {
             let _ = self.conn.delete_property(self.root, p);
         }
+        self.tabs.destroy(&self.conn);
         let _ = self.conn.destroy_window(self.check_window);
         self.conn.flush()?;
         info!("BoringWM stopped without closing clients");